@@ -1,12 +1,16 @@
 #[cfg(feature = "allocator_api")]
 pub use std::alloc::Global as StdAllocator;
-use std::alloc::Layout;
-use std::ptr::NonNull;
+use std::alloc::{GlobalAlloc, Layout};
+use std::ptr::{copy_nonoverlapping as copy_bytes, slice_from_raw_parts_mut, NonNull};
 
 #[cfg(not(feature = "allocator_api"))]
 pub use stable_allocator::StdAllocator;
 
 use crate::error::Error;
+use crate::notebook::Notebook;
+
+#[cfg(all(feature = "allocator-api2", not(feature = "allocator_api")))]
+pub(crate) mod api2_allocator;
 
 #[cfg(feature = "allocator_api")]
 pub(crate) mod nightly_allocator;
@@ -17,31 +21,262 @@ pub(crate) mod stable_allocator;
 /// This trait mirrors the Allocator trait from the allocator_api. The allocator_api is only
 /// available on nightly. This is an abstraction over the presence of the allocator_api to allow the
 /// library to work on stable rust.
+///
+/// On stable, implement this trait to back notebooks with your own allocator. Only `allocate` and
+/// `deallocate` are required. On nightly, implement `std::alloc::Allocator` instead, every
+/// Allocator is a BookcaseAllocator.
 pub trait BookcaseAllocator {
     fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, Error>;
-    fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, Error>;
+
+    /// # Safety
+    ///
+    /// The memory must have been allocated by this allocator with the same layout.
     unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
 
+    #[inline]
+    fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, Error> {
+        let ptr = self.allocate(layout)?;
+
+        unsafe {
+            ptr.as_ptr().cast::<u8>().write_bytes(0, layout.size());
+        }
+
+        Ok(ptr)
+    }
+
+    /// # Safety
+    ///
+    /// The memory must have been allocated by this allocator with the old layout.
+    #[inline]
     unsafe fn grow(
         &self,
         ptr: NonNull<u8>,
         old_layout: Layout,
         new_layout: Layout,
-    ) -> Result<NonNull<[u8]>, Error>;
+    ) -> Result<NonNull<[u8]>, Error> {
+        if old_layout.size() > new_layout.size() {
+            return Err(Error::GrowError(old_layout.size(), new_layout.size()));
+        }
 
+        let new_ptr = self.allocate(new_layout)?;
+
+        copy_bytes(ptr.as_ptr(), new_ptr.as_ptr().cast(), old_layout.size());
+        self.deallocate(ptr, old_layout);
+        Ok(new_ptr)
+    }
+
+    /// # Safety
+    ///
+    /// The memory must have been allocated by this allocator with the old layout.
+    #[inline]
     unsafe fn grow_zeroed(
         &self,
         ptr: NonNull<u8>,
         old_layout: Layout,
         new_layout: Layout,
-    ) -> Result<NonNull<[u8]>, Error>;
+    ) -> Result<NonNull<[u8]>, Error> {
+        let new_ptr = self.grow(ptr, old_layout, new_layout)?;
+
+        new_ptr
+            .as_ptr()
+            .cast::<u8>()
+            .add(old_layout.size())
+            .write_bytes(0, new_layout.size() - old_layout.size());
+
+        Ok(new_ptr)
+    }
 
+    /// # Safety
+    ///
+    /// The memory must have been allocated by this allocator with the old layout.
+    #[inline]
     unsafe fn shrink(
         &self,
         ptr: NonNull<u8>,
         old_layout: Layout,
         new_layout: Layout,
-    ) -> Result<NonNull<[u8]>, Error>;
+    ) -> Result<NonNull<[u8]>, Error> {
+        if old_layout.size() < new_layout.size() {
+            return Err(Error::ShrinkError(old_layout.size(), new_layout.size()));
+        }
+
+        let new_ptr = self.allocate(new_layout)?;
+
+        copy_bytes(ptr.as_ptr(), new_ptr.as_ptr().cast(), new_layout.size());
+        self.deallocate(ptr, old_layout);
+        Ok(new_ptr)
+    }
+
+    #[inline(always)]
+    fn by_ref(&self) -> &Self where Self: Sized {
+        self
+    }
+}
+
+/// Allocators where every value can free the memory of every other value of the type. Notebooks
+/// backed by them can be combined safely.
+///
+/// # Safety
+///
+/// Memory allocated by any value of the type must be freeable by any other value of the type.
+pub unsafe trait InterchangeableAllocator: BookcaseAllocator {}
+
+unsafe impl InterchangeableAllocator for StdAllocator {}
+
+unsafe impl<G: GlobalAlloc> InterchangeableAllocator for GlobalAllocator<G> {}
+
+unsafe impl<'a, A: InterchangeableAllocator> InterchangeableAllocator for BorrowedAllocator<'a, A> {}
+
+/// Backs notebooks with any global allocator, for example `std::alloc::System`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GlobalAllocator<G: GlobalAlloc>(pub G);
+
+/// Backs a notebook with another notebook. Pages of the outer notebook are allocated in the inner
+/// notebook, so dropping the outer notebook only gives the pages back to the inner notebook.
+///
+/// The allocator is only Sync when the inner notebook is. A personal inner notebook can back a
+/// public notebook, but that public notebook is then not Sync and cannot be shared between
+/// threads. Use a public inner notebook for that.
+pub struct NotebookAllocator<'book, N: Notebook>(pub &'book N);
+
+impl<'book, N: Notebook> Clone for NotebookAllocator<'book, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'book, N: Notebook> Copy for NotebookAllocator<'book, N> {}
+
+/// Backs a notebook with a borrowed allocator. Child notebooks use it to share the allocator of
+/// their parent.
+pub struct BorrowedAllocator<'a, A: BookcaseAllocator>(pub &'a A);
+
+impl<'a, A: BookcaseAllocator> Clone for BorrowedAllocator<'a, A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, A: BookcaseAllocator> Copy for BorrowedAllocator<'a, A> {}
+
+impl<'a, A: BookcaseAllocator> BookcaseAllocator for BorrowedAllocator<'a, A> {
+    #[inline(always)]
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, Error> {
+        self.0.allocate(layout)
+    }
+
+    #[inline(always)]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.0.deallocate(ptr, layout)
+    }
+
+    #[inline(always)]
+    fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, Error> {
+        self.0.allocate_zeroed(layout)
+    }
+
+    #[inline(always)]
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, Error> {
+        self.0.grow(ptr, old_layout, new_layout)
+    }
+
+    #[inline(always)]
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, Error> {
+        self.0.grow_zeroed(ptr, old_layout, new_layout)
+    }
+
+    #[inline(always)]
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, Error> {
+        self.0.shrink(ptr, old_layout, new_layout)
+    }
+}
+
+/// Builds the fat pointer to the allocated bytes.
+#[inline(always)]
+pub(crate) fn slice_ptr(ptr: NonNull<u8>, len: usize) -> NonNull<[u8]> {
+    unsafe { NonNull::new_unchecked(slice_from_raw_parts_mut(ptr.as_ptr(), len)) }
+}
+
+/// Zero sized allocations never reach the backing allocator, they get a well aligned dangling
+/// pointer instead.
+#[inline(always)]
+pub(crate) fn dangling(layout: Layout) -> NonNull<[u8]> {
+    unsafe { slice_ptr(NonNull::new_unchecked(layout.align() as *mut u8), 0) }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::alloc::System;
+
+    use crate::*;
+
+    #[test]
+    fn test_global_allocator() {
+        let notebook = PersonalMultiNotebook::<_, Pencil>::new(
+            GlobalAllocator(System),
+            SizeStrategy::WordsPerPage(4),
+            GrowthStrategy::Constant,
+        );
+
+        let a = notebook.alloc_init(7u64).expect(line_str!());
+        let s = notebook.new(String::from("system")).expect(line_str!());
+
+        assert_eq!(7, *a);
+        assert_eq!("system", *s);
+    }
+
+    #[test]
+    fn test_notebook_backed_by_notebook() {
+        let inner = PersonalMultiNotebook::<_, Pen>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(64),
+            GrowthStrategy::Constant,
+        );
+
+        {
+            let outer = PersonalMultiNotebook::<_, Pencil>::new(
+                NotebookAllocator(&inner),
+                SizeStrategy::WordsPerPage(4),
+                GrowthStrategy::Constant,
+            );
+
+            for idx in 0..16u64 {
+                assert_eq!(idx, *outer.alloc_init(idx).expect(line_str!()));
+            }
+
+            let outer_pages = outer.clone_chapters()[3].len();
+
+            assert_eq!(4, outer_pages);
+        }
+
+        // all of the outer pages fit in a single page of the inner notebook
+        assert_eq!(1, inner.clone_chapters()[3].len());
+    }
+
+    fn assert_sync<T: Sync>() {}
+
+    #[test]
+    fn ensure_only_public_notebooks_back_shared_notebooks() {
+        type Personal = PersonalMultiNotebook<StdAllocator, Pen>;
+        type Public = PublicMultiNotebook<StdAllocator, Pen>;
 
-    fn by_ref(&self) -> &Self where Self: Sized;
+        assert_not_sync!(NotebookAllocator<'static, Personal>);
+        assert_not_sync!(PublicMultiNotebook<NotebookAllocator<'static, Personal>, Pen>);
+        assert_sync::<PublicMultiNotebook<NotebookAllocator<'static, Public>, Pen>>();
+    }
 }
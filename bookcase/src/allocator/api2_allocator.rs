@@ -0,0 +1,77 @@
+use std::alloc::Layout;
+use std::ptr::NonNull;
+
+use allocator_api2::alloc::{AllocError, Allocator};
+
+use crate::allocator::{slice_ptr, BookcaseAllocator};
+use crate::notebook::{MultiNotebook, Notebook};
+use crate::page::Utensil;
+
+/// Allows allocator-api2 collections to live in a notebook on stable, e.g.
+/// `allocator_api2::vec::Vec::new_in(&notebook)` or a hashbrown map.
+unsafe impl<A, U, L> Allocator for &MultiNotebook<A, U, L>
+where
+    A: BookcaseAllocator,
+    U: Utensil,
+    MultiNotebook<A, U, L>: Notebook,
+{
+    #[inline(always)]
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.try_alloc_layout(layout)
+            .map(|ptr| slice_ptr(ptr, layout.size()))
+            .map_err(|_| AllocError)
+    }
+
+    #[inline(always)]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.dealloc_layout(ptr, layout);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::RandomState;
+
+    use allocator_api2::boxed::Box;
+    use allocator_api2::vec::Vec;
+    use hashbrown::HashMap;
+
+    use crate::*;
+
+    #[test]
+    fn test_api2_vec_and_box_in_notebook() {
+        let notebook = PersonalMultiNotebook::<_, Pencil>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(16),
+            GrowthStrategy::Exponential,
+        );
+
+        let mut v = Vec::new_in(&notebook);
+        let boxed = Box::new_in(String::from("boxed"), &notebook);
+
+        for idx in 0..100u64 {
+            v.push(idx);
+        }
+
+        assert_eq!((0..100).sum::<u64>(), v.iter().sum());
+        assert_eq!("boxed", *boxed);
+    }
+
+    #[test]
+    fn test_hashbrown_map_in_notebook() {
+        let notebook = PublicMultiNotebook::<_, Pencil>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(16),
+            GrowthStrategy::Exponential,
+        );
+
+        let mut map = HashMap::with_hasher_in(RandomState::new(), &notebook);
+
+        for idx in 0..100u64 {
+            map.insert(idx, idx * 2);
+        }
+
+        assert_eq!(100, map.len());
+        assert_eq!(Some(&84), map.get(&42));
+    }
+}
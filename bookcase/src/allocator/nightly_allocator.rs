@@ -1,7 +1,15 @@
-use std::alloc::{Allocator, Layout};
+use std::alloc::{AllocError, Allocator, GlobalAlloc, Layout};
 use std::ptr::NonNull;
-use crate::allocator::BookcaseAllocator;
+use crate::allocator::{
+    dangling,
+    slice_ptr,
+    BookcaseAllocator,
+    GlobalAllocator,
+    NotebookAllocator,
+};
 use crate::error::Error;
+use crate::notebook::{MultiNotebook, Notebook};
+use crate::page::Utensil;
 
 impl<T: Allocator> BookcaseAllocator for T {
     #[inline(always)]
@@ -39,3 +47,139 @@ impl<T: Allocator> BookcaseAllocator for T {
         Allocator::by_ref(&self)
     }
 }
+
+unsafe impl<G: GlobalAlloc> Allocator for GlobalAllocator<G> {
+    #[inline(always)]
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        if layout.size() == 0 {
+            return Ok(dangling(layout));
+        }
+
+        NonNull::new(unsafe { self.0.alloc(layout) })
+            .map(|ptr| slice_ptr(ptr, layout.size()))
+            .ok_or(AllocError)
+    }
+
+    #[inline(always)]
+    fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        if layout.size() == 0 {
+            return Ok(dangling(layout));
+        }
+
+        NonNull::new(unsafe { self.0.alloc_zeroed(layout) })
+            .map(|ptr| slice_ptr(ptr, layout.size()))
+            .ok_or(AllocError)
+    }
+
+    #[inline(always)]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        // zero sized allocations were never given to the global allocator
+        if layout.size() != 0 {
+            self.0.dealloc(ptr.as_ptr(), layout);
+        }
+    }
+}
+
+unsafe impl<'book, N: Notebook> Allocator for NotebookAllocator<'book, N> {
+    #[inline(always)]
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.0
+            .try_alloc_layout(layout)
+            .map(|ptr| slice_ptr(ptr, layout.size()))
+            .map_err(|_| AllocError)
+    }
+
+    #[inline(always)]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.0.dealloc_layout(ptr, layout);
+    }
+}
+
+/// Allows allocator aware collections to live in a notebook, e.g. `Vec::new_in(&notebook)`.
+unsafe impl<A, U, L> Allocator for &MultiNotebook<A, U, L>
+where
+    A: BookcaseAllocator,
+    U: Utensil,
+    MultiNotebook<A, U, L>: Notebook,
+{
+    #[inline(always)]
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.try_alloc_layout(layout)
+            .map(|ptr| slice_ptr(ptr, layout.size()))
+            .map_err(|_| AllocError)
+    }
+
+    #[inline(always)]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.dealloc_layout(ptr, layout);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+
+    use crate::*;
+
+    #[test]
+    fn test_vec_in_personal_notebook() {
+        let notebook = PersonalMultiNotebook::<_, Pencil>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(16),
+            GrowthStrategy::Exponential,
+        );
+
+        let mut v = Vec::new_in(&notebook);
+
+        for idx in 0..100u64 {
+            v.push(idx);
+        }
+
+        assert_eq!((0..100).sum::<u64>(), v.iter().sum());
+    }
+
+    #[test]
+    fn test_box_and_string_in_personal_notebook() {
+        let notebook = PersonalMultiNotebook::<_, Pencil>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(16),
+            GrowthStrategy::Constant,
+        );
+
+        let boxed = Box::new_in(String::from("boxed"), &notebook);
+        let mut bytes = Vec::new_in(&notebook);
+
+        bytes.extend_from_slice(b"Hello, ");
+        bytes.extend_from_slice(b"World!");
+
+        assert_eq!("boxed", *boxed);
+        assert_eq!("Hello, World!", std::str::from_utf8(&bytes).expect(line_str!()));
+    }
+
+    #[test]
+    fn test_vec_in_public_notebook() {
+        let notebook = Arc::new(PublicMultiNotebook::<_, Pencil>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(16),
+            GrowthStrategy::Exponential,
+        ));
+
+        let threads: Vec<_> = (0..4u64)
+            .map(|t| {
+                let notebook = notebook.clone();
+
+                thread::spawn(move || {
+                    let mut v = Vec::new_in(&*notebook);
+
+                    v.extend((0..50).map(|idx| idx * t));
+                    v.iter().sum::<u64>()
+                })
+            })
+            .collect();
+
+        for (t, thread) in threads.into_iter().enumerate() {
+            assert_eq!((0..50).sum::<u64>() * t as u64, thread.join().expect(line_str!()));
+        }
+    }
+}
@@ -1,8 +1,15 @@
-use std::alloc::{alloc, alloc_zeroed, dealloc, Layout, realloc};
+use std::alloc::{alloc, alloc_zeroed, dealloc, GlobalAlloc, Layout, realloc};
 use std::ptr::{copy_nonoverlapping as copy_bytes, NonNull};
 
-use crate::allocator::BookcaseAllocator;
+use crate::allocator::{
+    dangling,
+    slice_ptr,
+    BookcaseAllocator,
+    GlobalAllocator,
+    NotebookAllocator,
+};
 use crate::error::Error;
+use crate::notebook::Notebook;
 
 #[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
 #[repr(C)]
@@ -17,6 +24,7 @@ impl<T> Slice<T> {
     }
 }
 
+#[derive(Clone, Copy, Debug, Default)]
 pub struct StdAllocator;
 
 impl BookcaseAllocator for StdAllocator {
@@ -39,6 +47,10 @@ impl BookcaseAllocator for StdAllocator {
 
     #[inline(always)]
     fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, Error> {
+        if layout.size() == 0 {
+            return self.allocate(layout);
+        }
+
         let ptr = unsafe { alloc_zeroed(layout) };
 
         if ptr.is_null() {
@@ -50,7 +62,10 @@ impl BookcaseAllocator for StdAllocator {
 
     #[inline(always)]
     unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
-        unsafe { dealloc(ptr.as_ptr(), layout); }
+        // zero sized allocations were never given to the global allocator
+        if layout.size() != 0 {
+            unsafe { dealloc(ptr.as_ptr(), layout); }
+        }
     }
 
     #[inline]
@@ -132,3 +147,47 @@ impl BookcaseAllocator for StdAllocator {
         self
     }
 }
+
+impl<G: GlobalAlloc> BookcaseAllocator for GlobalAllocator<G> {
+    #[inline(always)]
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, Error> {
+        if layout.size() == 0 {
+            return Ok(dangling(layout));
+        }
+
+        NonNull::new(unsafe { self.0.alloc(layout) })
+            .map(|ptr| slice_ptr(ptr, layout.size()))
+            .ok_or(Error::AllocError)
+    }
+
+    #[inline(always)]
+    fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, Error> {
+        if layout.size() == 0 {
+            return Ok(dangling(layout));
+        }
+
+        NonNull::new(unsafe { self.0.alloc_zeroed(layout) })
+            .map(|ptr| slice_ptr(ptr, layout.size()))
+            .ok_or(Error::AllocError)
+    }
+
+    #[inline(always)]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        // zero sized allocations were never given to the global allocator
+        if layout.size() != 0 {
+            self.0.dealloc(ptr.as_ptr(), layout);
+        }
+    }
+}
+
+impl<'book, N: Notebook> BookcaseAllocator for NotebookAllocator<'book, N> {
+    #[inline(always)]
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, Error> {
+        self.0.try_alloc_layout(layout).map(|ptr| slice_ptr(ptr, layout.size()))
+    }
+
+    #[inline(always)]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.0.dealloc_layout(ptr, layout);
+    }
+}
@@ -0,0 +1,290 @@
+use core::alloc::Layout;
+use core::cell::RefCell;
+use core::ptr::{null_mut, NonNull};
+use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+use crate::allocator::BookcaseAllocator;
+use crate::chapter::Chapter;
+use crate::error::Error;
+use crate::page::{padding, Utensil};
+
+/// A page which is written over all at once so people can race through it with compare and swap.
+struct Region {
+    cursor: AtomicUsize,
+    end: usize,
+}
+
+impl Region {
+    /// Hands out at least the layout and up to the given number of bytes. Returns the start and
+    /// end address of the memory, the start is aligned to the layout.
+    #[inline(always)]
+    fn alloc_range(&self, layout: Layout, bytes: usize) -> Option<(usize, usize)> {
+        let mut cursor = self.cursor.load(Ordering::Relaxed);
+
+        loop {
+            let start = cursor.checked_add(padding(cursor, layout.align()))?;
+
+            if start.checked_add(layout.size())? > self.end {
+                return None;
+            }
+
+            let next = start.saturating_add(bytes.max(layout.size())).min(self.end);
+
+            // The memory itself is not shared, so the cursor needs no ordering of its own.
+            match self.cursor.compare_exchange_weak(
+                cursor,
+                next,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Some((start, next)),
+                Err(current) => cursor = current,
+            }
+        }
+    }
+
+    /// Stops handing out memory and returns the address of the unused memory.
+    #[inline(always)]
+    fn close(&self) -> usize {
+        self.cursor.swap(self.end, Ordering::Relaxed)
+    }
+}
+
+/// Lets people allocate in the newest page of a chapter without locking. Only the lock holder may
+/// start a new region, and regions are only freed with exclusive access, so a region can never be
+/// freed while someone is bumping through it.
+pub(crate) struct Bump {
+    current: AtomicPtr<Region>,
+    /// Boxed so a region stays in place while the vector grows.
+    #[allow(clippy::vec_box)]
+    regions: RefCell<Vec<Box<Region>>>,
+}
+
+impl Bump {
+    pub(crate) fn new() -> Bump {
+        Bump {
+            current: AtomicPtr::new(null_mut()),
+            regions: RefCell::new(vec![]),
+        }
+    }
+
+    /// The lock-free path, fails when the current region is full.
+    #[inline(always)]
+    pub(crate) fn alloc(&self, layout: Layout) -> Option<NonNull<u8>> {
+        self.alloc_range(layout, layout.size())
+            .and_then(|(start, _)| NonNull::new(start as *mut u8))
+    }
+
+    /// The lock-free path for handing out at least the layout and up to the given number of
+    /// bytes, fails when the current region is full.
+    #[inline(always)]
+    pub(crate) fn alloc_range(&self, layout: Layout, bytes: usize) -> Option<(usize, usize)> {
+        let region = self.current.load(Ordering::Acquire);
+
+        if region.is_null() {
+            None
+        } else {
+            unsafe { (*region).alloc_range(layout, bytes) }
+        }
+    }
+
+    /// The locked path. The caller must hold the lock of the chapter.
+    pub(crate) fn alloc_locked<U: Utensil>(
+        &self,
+        chapter: &mut Chapter<U>,
+        allocator: &dyn BookcaseAllocator,
+        layout: Layout,
+        page_bytes: usize,
+    ) -> Result<NonNull<u8>, Error> {
+        let (start, _) =
+            self.alloc_range_locked(chapter, allocator, layout, layout.size(), page_bytes)?;
+
+        // pages are never at the null address
+        unsafe { Ok(NonNull::new_unchecked(start as *mut u8)) }
+    }
+
+    /// The locked path for handing out at least the layout and up to the given number of bytes.
+    /// Memory given back to the written pages is used first, only then does a blank page become
+    /// the new region. The caller must hold the lock of the chapter.
+    pub(crate) fn alloc_range_locked<U: Utensil>(
+        &self,
+        chapter: &mut Chapter<U>,
+        allocator: &dyn BookcaseAllocator,
+        layout: Layout,
+        bytes: usize,
+        page_bytes: usize,
+    ) -> Result<(usize, usize), Error> {
+        // another person may have started a new region while this one waited for the lock
+        if let Some(range) = self.alloc_range(layout, bytes) {
+            return Ok(range);
+        }
+
+        self.retire(chapter);
+
+        if let Ok(chunk) = Layout::from_size_align(bytes.max(layout.size()), layout.align()) {
+            if let Some(ptr) = chapter.alloc_written(chunk) {
+                return Ok((ptr as usize, ptr as usize + chunk.size()));
+            }
+        }
+
+        if let Some(ptr) = chapter.alloc_written(layout) {
+            return Ok((ptr as usize, ptr as usize + layout.size()));
+        }
+
+        let (start, end) = chapter.alloc_whole_page(allocator, layout, page_bytes)?;
+        let region = Box::new(Region { cursor: AtomicUsize::new(start), end });
+
+        // the page is aligned to the layout and fits its size
+        let range = region
+            .alloc_range(layout, bytes)
+            .ok_or(Error::OversizedError(layout.size()))?;
+
+        self.current.store(&*region as *const Region as *mut Region, Ordering::Release);
+        self.regions.borrow_mut().push(region);
+        Ok(range)
+    }
+
+    /// Stops the current region and gives its unused memory back to its page. The caller must
+    /// hold the write lock.
+    pub(crate) fn retire<U: Utensil>(&self, chapter: &mut Chapter<U>) {
+        let region = self.current.swap(null_mut(), Ordering::AcqRel);
+
+        if !region.is_null() {
+            let region = unsafe { &*region };
+            let cursor = region.close();
+
+            if cursor < region.end {
+                chapter.unwrite(cursor, region.end);
+            }
+        }
+    }
+
+    /// Retires the current region and frees every region.
+    pub(crate) fn clear<U: Utensil>(&mut self, chapter: &mut Chapter<U>) {
+        self.retire(chapter);
+        self.regions.get_mut().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use std::thread;
+
+    use crate::*;
+
+    #[test]
+    fn test_lock_free_allocations_do_not_overlap() {
+        let notebook = Arc::new(PublicMultiNotebook::<_, Pen>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(16),
+            GrowthStrategy::Constant,
+        ));
+
+        let workers: Vec<_> = (0..8u64)
+            .map(|t| {
+                let notebook = notebook.clone();
+
+                thread::spawn(move || {
+                    (0..500u64)
+                        .map(|idx| notebook.alloc_init(t * 1000 + idx).expect(line_str!()))
+                        .map(|value| value as *mut u64 as usize)
+                        .collect::<Vec<usize>>()
+                })
+            })
+            .collect();
+
+        let mut addrs = HashSet::new();
+
+        for (t, worker) in workers.into_iter().enumerate() {
+            for (idx, addr) in worker.join().expect(line_str!()).into_iter().enumerate() {
+                assert!(addrs.insert(addr));
+                assert_eq!((t * 1000 + idx) as u64, unsafe { *(addr as *const u64) });
+            }
+        }
+    }
+
+    #[test]
+    fn test_marker_erases_lock_free_allocations() {
+        let mut notebook = PublicMultiNotebook::<_, Pen>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(8),
+            GrowthStrategy::Constant,
+        );
+
+        notebook.alloc_init(1u64).expect(line_str!());
+
+        let marker = notebook.marker().expect(line_str!());
+        let after = notebook.alloc_init(2u64).expect(line_str!()) as *mut u64 as usize;
+
+        notebook.alloc_init(3u64).expect(line_str!());
+        notebook.rewind(marker);
+
+        assert_eq!(after, notebook.alloc_init(4u64).expect(line_str!()) as *mut u64 as usize);
+    }
+
+    #[test]
+    fn test_marker_keeps_rest_of_page() {
+        let notebook = PublicMultiNotebook::<_, Pen>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(8),
+            GrowthStrategy::Constant,
+        );
+
+        let first = notebook.alloc_init(1u64).expect(line_str!()) as *mut u64 as usize;
+
+        notebook.marker().expect(line_str!());
+
+        let second = notebook.alloc_init(2u64).expect(line_str!()) as *mut u64 as usize;
+
+        assert_eq!(first + 8, second);
+        assert_eq!(1, notebook.clone_chapters()[3].len());
+    }
+
+    #[test]
+    fn test_public_eraser_frees_most_recent_allocation() {
+        let notebook = PublicMultiNotebook::<_, Eraser>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(8),
+            GrowthStrategy::Constant,
+        )
+        .with_thread_cache(64);
+
+        notebook.alloc_init(1u64).expect(line_str!());
+
+        let value = notebook.alloc_init(2u64).expect(line_str!());
+        let addr = value as *mut u64 as usize;
+
+        assert!(unsafe { notebook.dealloc(value) });
+        assert_eq!(addr, notebook.alloc_init(3u64).expect(line_str!()) as *mut u64 as usize);
+    }
+
+    #[test]
+    fn test_lock_free_mono_notebook_is_aligned() {
+        let typed_notebook = Arc::new(PublicMonoNotebook::<_, Pencil, (u8, u64)>::new(
+            StdAllocator,
+            SizeStrategy::ItemsPerPage(8),
+            GrowthStrategy::Linear(1),
+        ));
+
+        let workers: Vec<_> = (0..4u8)
+            .map(|t| {
+                let typed_notebook = typed_notebook.clone();
+
+                thread::spawn(move || {
+                    for idx in 0..100u64 {
+                        let value = typed_notebook.alloc_init_t((t, idx)).expect(line_str!());
+
+                        assert_eq!(0, value as *mut (u8, u64) as usize % 8);
+                        assert_eq!((t, idx), *value);
+                    }
+                })
+            })
+            .collect();
+
+        for worker in workers {
+            worker.join().expect(line_str!());
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use core::alloc::Layout;
+use core::cell::RefCell;
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::page::padding;
+
+/// How many chunks a person keeps at once. The oldest chunk is forgotten when another is needed.
+const MAX_CHUNKS: usize = 16;
+
+/// Every notebook gets its own id, so a chunk can never be mistaken for one of another notebook.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Part of a page that only one person bumps through.
+struct Chunk {
+    notebook: usize,
+    chapter: usize,
+    epoch: usize,
+    cursor: usize,
+    end: usize,
+}
+
+thread_local! {
+    static CHUNKS: RefCell<Vec<Chunk>> = RefCell::new(Vec::new());
+}
+
+/// Lets every person allocate from their own chunk of a page without touching anything shared.
+/// Chunks are only valid for the epoch they were taken in, and the epoch moves on whenever the
+/// pages may be written over again, so a chunk can never outlive its memory.
+pub(crate) struct ThreadCache {
+    id: usize,
+    epoch: AtomicUsize,
+    chunk_bytes: usize,
+}
+
+impl ThreadCache {
+    pub(crate) fn new() -> ThreadCache {
+        ThreadCache {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            epoch: AtomicUsize::new(0),
+            chunk_bytes: 0,
+        }
+    }
+
+    pub(crate) fn with_chunk_bytes(mut self, chunk_bytes: usize) -> ThreadCache {
+        self.chunk_bytes = chunk_bytes;
+        self
+    }
+
+    pub(crate) fn chunk_bytes(&self) -> usize {
+        self.chunk_bytes
+    }
+
+    /// Values larger than half a chunk skip the cache, so a chunk is never mostly wasted when it
+    /// is replaced.
+    #[inline(always)]
+    pub(crate) fn fits(&self, layout: Layout) -> bool {
+        self.chunk_bytes > 0 && layout.size() <= self.chunk_bytes / 2
+    }
+
+    /// Bumps through the chunk of this person, fails when there is no valid chunk or it is full.
+    #[inline(always)]
+    pub(crate) fn alloc(&self, chapter: usize, layout: Layout) -> Option<NonNull<u8>> {
+        let epoch = self.epoch.load(Ordering::Acquire);
+
+        CHUNKS
+            .try_with(|chunks| {
+                let mut chunks = chunks.borrow_mut();
+                let chunk = chunks
+                    .iter_mut()
+                    .find(|c| c.notebook == self.id && c.chapter == chapter && c.epoch == epoch)?;
+
+                let start = chunk.cursor.checked_add(padding(chunk.cursor, layout.align()))?;
+                let next = start.checked_add(layout.size())?;
+
+                if next > chunk.end {
+                    return None;
+                }
+
+                chunk.cursor = next;
+                NonNull::new(start as *mut u8)
+            })
+            .ok()
+            .flatten()
+    }
+
+    /// Allocates the layout at the start of the range, and keeps the rest as the new chunk of this
+    /// person. The start must be aligned to the layout and the range must fit its size.
+    #[inline(always)]
+    pub(crate) fn refill(
+        &self,
+        chapter: usize,
+        layout: Layout,
+        range: (usize, usize),
+    ) -> NonNull<u8> {
+        let (start, end) = range;
+        let epoch = self.epoch.load(Ordering::Acquire);
+
+        let _ = CHUNKS.try_with(|chunks| {
+            let mut chunks = chunks.borrow_mut();
+
+            chunks.retain(|c| c.notebook != self.id || c.chapter != chapter);
+
+            if chunks.len() >= MAX_CHUNKS {
+                chunks.remove(0);
+            }
+
+            chunks.push(Chunk {
+                notebook: self.id,
+                chapter,
+                epoch,
+                cursor: start + layout.size(),
+                end,
+            });
+        });
+
+        // pages are never at the null address
+        unsafe { NonNull::new_unchecked(start as *mut u8) }
+    }
+
+    /// Stops every person from using the chunks they already have.
+    #[inline(always)]
+    pub(crate) fn invalidate(&self) {
+        self.epoch.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use std::thread;
+
+    use crate::*;
+
+    #[test]
+    fn test_thread_cache_allocations_do_not_overlap() {
+        let notebook = Arc::new(
+            PublicMultiNotebook::<_, Pen>::new(
+                StdAllocator,
+                SizeStrategy::WordsPerPage(64),
+                GrowthStrategy::Constant,
+            )
+            .with_thread_cache(64),
+        );
+
+        let workers: Vec<_> = (0..8u64)
+            .map(|t| {
+                let notebook = notebook.clone();
+
+                thread::spawn(move || {
+                    (0..500u64)
+                        .map(|idx| notebook.alloc_init(t * 1000 + idx).expect(line_str!()))
+                        .map(|value| value as *mut u64 as usize)
+                        .collect::<Vec<usize>>()
+                })
+            })
+            .collect();
+
+        let mut addrs = HashSet::new();
+
+        for (t, worker) in workers.into_iter().enumerate() {
+            for (idx, addr) in worker.join().expect(line_str!()).into_iter().enumerate() {
+                assert!(addrs.insert(addr));
+                assert_eq!((t * 1000 + idx) as u64, unsafe { *(addr as *const u64) });
+            }
+        }
+    }
+
+    #[test]
+    fn test_thread_cache_is_dropped_on_reset() {
+        let mut notebook = PublicMonoNotebook::<_, Pen, u64>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(64),
+            GrowthStrategy::Constant,
+        )
+        .with_thread_cache(64);
+
+        let first = notebook.alloc_init_t(1).expect(line_str!()) as *mut u64;
+        let second = notebook.alloc_init_t(2).expect(line_str!()) as *mut u64;
+
+        assert_eq!(first.wrapping_add(1), second);
+
+        notebook.reset();
+
+        let third = notebook.alloc_init_t(3).expect(line_str!()) as *mut u64;
+
+        assert_eq!(first, third);
+    }
+
+    #[test]
+    fn test_large_values_skip_thread_cache() {
+        let notebook = PublicMultiNotebook::<_, Pen>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(64),
+            GrowthStrategy::Constant,
+        )
+        .with_thread_cache(64);
+
+        let small = notebook.alloc_init(1u64).expect(line_str!()) as *mut u64 as usize;
+        let large = notebook.alloc_init([2u64; 16]).expect(line_str!()) as *mut [u64; 16] as usize;
+        let next = notebook.alloc_init(3u64).expect(line_str!()) as *mut u64 as usize;
+
+        assert_eq!(small + 8, next);
+        assert!(large >= small + 64);
+    }
+}
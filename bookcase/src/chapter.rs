@@ -1,15 +1,18 @@
 use core::alloc::Layout;
 
 use crate::allocator::BookcaseAllocator;
+use crate::error::Error;
 use crate::page::{Page, Utensil};
 
 pub(crate) struct Chapter<U> {
     pages: Vec<Page<U>>,
+    /// Erased pages waiting to be written in again.
+    spare: Vec<Page<U>>,
 }
 
 impl<U: Utensil> Chapter<U> {
     pub(crate) fn new() -> Chapter<U> {
-        Chapter { pages: vec![] }
+        Chapter { pages: vec![], spare: vec![] }
     }
 
     pub(crate) fn pages(&self) -> &[Page<U>] {
@@ -19,33 +22,176 @@ impl<U: Utensil> Chapter<U> {
     pub(crate) fn alloc(
         &mut self,
         allocator: &dyn BookcaseAllocator,
-        t_size: usize,
-        t_align: usize,
+        layout: Layout,
         page_bytes: usize,
-    ) -> Option<*mut u8> {
-        if let Some(page) = self.pages.iter_mut().rev().find(|p| p.can_alloc(t_size)) {
-            Some(page.alloc(t_size))
+    ) -> Result<*mut u8, Error> {
+        if let Some(ptr) = self.alloc_written(layout) {
+            Ok(ptr)
         } else {
-            let layout = Layout::from_size_align(page_bytes, t_align).ok()?;
-            let mut page = Page::create(layout, allocator)?;
-            let ptr = page.alloc(t_size);
+            let mut page = self.blank_page(allocator, layout, page_bytes)?;
+            let ptr = page.alloc(layout);
 
             self.pages.push(page);
-            Some(ptr)
+            Ok(ptr)
         }
     }
 
-    pub(crate) fn dealloc(&mut self, ptr: *const u8) -> bool {
+    /// Only allocates in pages which are already being written in.
+    pub(crate) fn alloc_written(&mut self, layout: Layout) -> Option<*mut u8> {
+        self.pages
+            .iter_mut()
+            .rev()
+            .find(|p| p.can_alloc(layout))
+            .map(|p| p.alloc(layout))
+    }
+
+    /// Writes over all of a blank page fitting the layout at once, so its memory can be handed out
+    /// without going through the chapter. Returns the start and end address of the page.
+    pub(crate) fn alloc_whole_page(
+        &mut self,
+        allocator: &dyn BookcaseAllocator,
+        layout: Layout,
+        page_bytes: usize,
+    ) -> Result<(usize, usize), Error> {
+        let mut page = self.blank_page(allocator, layout, page_bytes)?;
+        let whole = Layout::from_size_align(page.size(), 1)?;
+        let start = page.alloc(whole) as usize;
+
+        self.pages.push(page);
+        Ok((start, start + whole.size()))
+    }
+
+    /// Takes a spare page fitting the layout, or creates one.
+    fn blank_page(
+        &mut self,
+        allocator: &dyn BookcaseAllocator,
+        layout: Layout,
+        page_bytes: usize,
+    ) -> Result<Page<U>, Error> {
+        if let Some(idx) = self.spare.iter().position(|p| p.can_alloc(layout)) {
+            return Ok(self.spare.remove(idx));
+        }
+
+        // Values larger than the strategies allow get a page to themselves.
+        let page_size = page_bytes.max(layout.size());
+
+        if page_size > isize::MAX as usize {
+            return Err(Error::OversizedError(page_size));
+        }
+
+        // The size is valid, so only the alignment can be at fault.
+        let page_layout = Layout::from_size_align(page_size, layout.align())
+            .map_err(|_| Error::AlignmentError(layout.align()))?;
+
+        Page::create(page_layout, allocator)
+    }
+
+    pub(crate) fn dealloc(&mut self, ptr: *const u8, layout: Layout) -> bool {
         if let Some(page) = self.pages.iter_mut().find(|p| p.can_dealloc(ptr)) {
-            page.dealloc(ptr);
+            page.dealloc(ptr, layout);
             true
         } else {
             false
         }
     }
 
-    pub(crate) fn destroy(&mut self, allocator: &dyn BookcaseAllocator) {
+    /// Gives the unused end of a page back to it, as if it was never written. Nothing happens
+    /// unless the page was written up to the end of the range.
+    pub(crate) fn unwrite(&mut self, start: usize, end: usize) {
+        let page = self
+            .pages
+            .iter_mut()
+            .find(|p| p.addr() <= start && p.addr() + p.offset() == end);
+
+        if let Some(page) = page {
+            let offset = start - page.addr();
+
+            page.rewind(offset);
+        }
+    }
+
+    /// The offset of every page in order.
+    pub(crate) fn mark(&self) -> Vec<usize> {
+        self.pages.iter().map(Page::offset).collect()
+    }
+
+    /// Pages created after the mark are kept, but erased completely.
+    pub(crate) fn rewind(&mut self, offsets: &[usize]) {
+        for (idx, page) in self.pages.iter_mut().enumerate() {
+            page.rewind(offsets.get(idx).copied().unwrap_or(0));
+        }
+    }
+
+    /// Pages past the length are given back to the allocator.
+    pub(crate) fn truncate(&mut self, len: usize, allocator: &dyn BookcaseAllocator) {
+        if len < self.pages.len() {
+            for mut page in self.pages.drain(len..) {
+                page.destroy(allocator);
+            }
+        }
+    }
+
+    /// Every page is kept, but erased completely.
+    pub(crate) fn reset(&mut self) {
         for page in self.pages.iter_mut() {
+            page.rewind(0);
+        }
+    }
+
+    /// Only the largest page is kept, erased completely. The rest are given back to the allocator.
+    pub(crate) fn trim(&mut self, allocator: &dyn BookcaseAllocator) {
+        let largest = self
+            .pages
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, p)| p.size())
+            .map(|(idx, _)| idx);
+
+        let mut kept = None;
+
+        for (idx, mut page) in self.pages.drain(..).enumerate() {
+            if Some(idx) == largest {
+                kept = Some(page);
+            } else {
+                page.destroy(allocator);
+            }
+        }
+
+        for mut page in self.spare.drain(..) {
+            page.destroy(allocator);
+        }
+
+        self.pages.extend(kept);
+        self.reset();
+    }
+
+    /// Moves every page of the other chapter to the end of this one.
+    pub(crate) fn append(&mut self, other: &mut Chapter<U>) {
+        self.pages.append(&mut other.pages);
+        self.spare.append(&mut other.spare);
+    }
+
+    /// Takes every spare page, leaving none behind.
+    pub(crate) fn take_spare(&mut self) -> Vec<Page<U>> {
+        std::mem::take(&mut self.spare)
+    }
+
+    /// Erases every page and adds them to the spare pages of the other chapter.
+    pub(crate) fn give_spare(&mut self, other: &mut Chapter<U>) {
+        for mut page in self.pages.drain(..) {
+            page.rewind(0);
+            other.spare.push(page);
+        }
+
+        other.spare.append(&mut self.spare);
+    }
+
+    pub(crate) fn set_spare(&mut self, spare: Vec<Page<U>>) {
+        self.spare = spare;
+    }
+
+    pub(crate) fn destroy(&mut self, allocator: &dyn BookcaseAllocator) {
+        for page in self.pages.iter_mut().chain(self.spare.iter_mut()) {
             page.destroy(allocator)
         }
     }
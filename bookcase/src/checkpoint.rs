@@ -0,0 +1,135 @@
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+
+use crate::allocator::BookcaseAllocator;
+use crate::notebook::{Marker, MonoNotebook, MultiNotebook};
+use crate::page::Utensil;
+
+/// Exclusive access to a notebook with the point it was taken at. Allocations go through the
+/// checkpoint, so they borrow it and must be gone before it can be rolled back. Dropping the
+/// checkpoint keeps everything allocated through it.
+///
+/// Taking a checkpoint borrows the notebook mutably, so references allocated before the checkpoint
+/// can't be used until it is rolled back or dropped. Anyone else holding the notebook could
+/// otherwise allocate memory the rollback erases. Values which must stay usable across a rollback,
+/// like the parsed part of a speculative parse, belong in another notebook.
+pub struct Checkpoint<'book, N> {
+    notebook: &'book mut N,
+    marker: Marker,
+}
+
+impl<'book, N> Checkpoint<'book, N> {
+    pub(crate) fn new(notebook: &'book mut N, marker: Marker) -> Checkpoint<'book, N> {
+        Checkpoint { notebook, marker }
+    }
+}
+
+impl<'book, A: BookcaseAllocator, U: Utensil, L> Checkpoint<'book, MultiNotebook<A, U, L>> {
+    /// Discards everything allocated after the checkpoint was taken.
+    pub fn rollback(self) {
+        self.notebook.rollback_impl(self.marker);
+    }
+}
+
+impl<'book, A: BookcaseAllocator, U: Utensil, T, L> Checkpoint<'book, MonoNotebook<A, U, T, L>> {
+    /// Discards everything allocated after the checkpoint was taken.
+    pub fn rollback(self) {
+        self.notebook.rollback_impl(self.marker);
+    }
+}
+
+impl<'book, N> fmt::Debug for Checkpoint<'book, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Checkpoint").field("marker", &self.marker).finish()
+    }
+}
+
+impl<'book, N> Deref for Checkpoint<'book, N> {
+    type Target = N;
+
+    fn deref(&self) -> &Self::Target {
+        self.notebook
+    }
+}
+
+impl<'book, N> DerefMut for Checkpoint<'book, N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.notebook
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_rollback_discards_allocations_and_pages() {
+        let mut notebook = PersonalMultiNotebook::<_, Pen>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(2),
+            GrowthStrategy::Constant,
+        );
+
+        notebook.alloc_init(1u64).expect(line_str!());
+
+        let checkpoint = notebook.checkpoint();
+        let next = checkpoint.alloc_init(2u64).expect(line_str!()) as *mut u64 as usize;
+
+        for idx in 0..6u64 {
+            checkpoint.alloc_init(idx).expect(line_str!());
+        }
+
+        assert_eq!(4, checkpoint.clone_chapters()[3].len());
+
+        checkpoint.rollback();
+
+        let reused = notebook.alloc_init(3u64).expect(line_str!()) as *mut u64 as usize;
+
+        assert_eq!(next, reused);
+        assert_eq!(1, notebook.clone_chapters()[3].len());
+    }
+
+    #[test]
+    fn test_dropped_checkpoint_keeps_allocations() {
+        let mut notebook = PersonalMultiNotebook::<_, Pen>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(2),
+            GrowthStrategy::Constant,
+        );
+
+        {
+            let checkpoint = notebook.checkpoint();
+
+            checkpoint.alloc_init(1u64).expect(line_str!());
+            checkpoint.alloc_init(2u64).expect(line_str!());
+        }
+
+        assert_eq!(
+            vec![1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0],
+            notebook.clone_chapters()[3][0],
+        );
+    }
+
+    #[test]
+    fn test_nested_checkpoints() {
+        let mut typed_notebook = PersonalMonoNotebook::<_, Eraser, u32>::new(
+            StdAllocator,
+            SizeStrategy::ItemsPerPage(8),
+            GrowthStrategy::Constant,
+        );
+
+        let mut outer = typed_notebook.checkpoint();
+        let first = outer.alloc_init_t(1).expect(line_str!()) as *mut u32 as usize;
+
+        let inner = outer.checkpoint();
+        let second = inner.alloc_init_t(2).expect(line_str!()) as *mut u32 as usize;
+
+        inner.rollback();
+
+        assert_eq!(second, outer.alloc_init_t(3).expect(line_str!()) as *mut u32 as usize);
+
+        outer.rollback();
+
+        assert_eq!(first, typed_notebook.alloc_init_t(4).expect(line_str!()) as *mut u32 as usize);
+    }
+}
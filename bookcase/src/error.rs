@@ -11,6 +11,18 @@ pub enum Error {
     GrowError(usize, usize),
 
     ShrinkError(usize, usize),
+
+    /// The size of a layout overflowed.
+    LayoutError,
+
+    /// A page of this many bytes is larger than the notebook allows.
+    OversizedError(usize),
+
+    /// No page can be created with this alignment.
+    AlignmentError(usize),
+
+    /// Another person panicked while writing in the notebook.
+    PoisonError,
 }
 
 impl std::error::Error for Error {}
@@ -30,6 +42,14 @@ impl Display for Error {
             Error::ShrinkError(old, new) => fmt.write_str(&format!(
                 "old size {} is smaller than new size {}", old, new
             )),
+            Error::LayoutError => fmt.write_str("layout size overflowed"),
+            Error::OversizedError(size) => fmt.write_str(&format!(
+                "page size {} is too large", size
+            )),
+            Error::AlignmentError(align) => fmt.write_str(&format!(
+                "alignment {} is not supported", align
+            )),
+            Error::PoisonError => fmt.write_str("notebook lock was poisoned"),
         }
     }
 }
@@ -40,3 +60,42 @@ impl From<std::alloc::AllocError> for Error {
         Error::AllocError(error)
     }
 }
+
+impl From<std::alloc::LayoutError> for Error {
+    fn from(_: std::alloc::LayoutError) -> Self {
+        Error::LayoutError
+    }
+}
+
+/// The error from moving a value into a notebook. The value is handed back instead of being
+/// dropped so the caller can try again or put it somewhere else.
+pub struct NewError<T> {
+    pub error: Error,
+    pub value: T,
+}
+
+impl<T> NewError<T> {
+    pub fn into_value(self) -> T {
+        self.value
+    }
+}
+
+impl<T> std::fmt::Debug for NewError<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> Result {
+        fmt.debug_struct("NewError").field("error", &self.error).finish_non_exhaustive()
+    }
+}
+
+impl<T> Display for NewError<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> Result {
+        Display::fmt(&self.error, fmt)
+    }
+}
+
+impl<T> std::error::Error for NewError<T> {}
+
+impl<T> From<NewError<T>> for Error {
+    fn from(error: NewError<T>) -> Self {
+        error.error
+    }
+}
@@ -0,0 +1,121 @@
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::ptr::NonNull;
+
+/// A notebook which can no longer be written in. The only way to read it is through the root
+/// chosen when it was frozen, so any number of people can read it at the same time without
+/// locking.
+pub struct FrozenNotebook<N, R: ?Sized = ()> {
+    notebook: NonNull<N>,
+    root: NonNull<R>,
+    _owns_notebook: PhantomData<N>,
+}
+
+impl<N> FrozenNotebook<N> {
+    pub(crate) fn new(notebook: N) -> FrozenNotebook<N> {
+        FrozenNotebook::with_root(notebook, |_| &())
+    }
+}
+
+impl<N, R: ?Sized> FrozenNotebook<N, R> {
+    pub(crate) fn with_root<F>(notebook: N, f: F) -> FrozenNotebook<N, R>
+    where
+        F: for<'book> FnOnce(&'book N) -> &'book R,
+    {
+        // the notebook is boxed, so references into it stay valid while it is frozen
+        let notebook = unsafe { NonNull::new_unchecked(Box::into_raw(Box::new(notebook))) };
+        let root = NonNull::from(f(unsafe { notebook.as_ref() }));
+
+        FrozenNotebook {
+            notebook,
+            root,
+            _owns_notebook: PhantomData,
+        }
+    }
+
+    /// The value chosen when the notebook was frozen.
+    pub fn root(&self) -> &R {
+        unsafe { self.root.as_ref() }
+    }
+
+    /// Allows writing in the notebook again. The root can no longer be read.
+    pub fn thaw(self) -> N {
+        let frozen = ManuallyDrop::new(self);
+
+        unsafe { *Box::from_raw(frozen.notebook.as_ptr()) }
+    }
+}
+
+impl<N, R: ?Sized> Drop for FrozenNotebook<N, R> {
+    fn drop(&mut self) {
+        unsafe {
+            drop(Box::from_raw(self.notebook.as_ptr()));
+        }
+    }
+}
+
+// Only the root can be read, the notebook is only touched again when it is dropped or thawed.
+unsafe impl<N: Send, R: ?Sized + Sync> Send for FrozenNotebook<N, R> {}
+
+unsafe impl<N, R: ?Sized + Sync> Sync for FrozenNotebook<N, R> {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+
+    use crate::*;
+
+    fn assert_sync<T: Sync>() {}
+
+    #[test]
+    fn ensure_frozen_notebooks_are_sync() {
+        assert_sync::<FrozenNotebook<PersonalMultiNotebook<StdAllocator, Pen>>>();
+        assert_sync::<FrozenNotebook<PersonalMonoNotebook<StdAllocator, Pen, u64>, [u64]>>();
+    }
+
+    #[test]
+    fn test_frozen_notebook_is_read_by_many_threads() {
+        let notebook = PersonalMultiNotebook::<_, Pen>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(8),
+            GrowthStrategy::Constant,
+        );
+
+        let frozen = Arc::new(notebook.freeze_with(|notebook| {
+            &*notebook.alloc_slice_fill_with(100, |idx| idx as u64).expect(line_str!())
+        }));
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let frozen = frozen.clone();
+
+                thread::spawn(move || frozen.root().iter().sum::<u64>())
+            })
+            .collect();
+
+        for reader in readers {
+            assert_eq!((0..100).sum::<u64>(), reader.join().expect(line_str!()));
+        }
+    }
+
+    #[test]
+    fn test_thawed_notebook_can_be_written_again() {
+        let typed_notebook = PersonalMonoNotebook::<_, Pen, u32>::new(
+            StdAllocator,
+            SizeStrategy::ItemsPerPage(4),
+            GrowthStrategy::Constant,
+        );
+
+        let frozen = typed_notebook.freeze_with(|typed_notebook| {
+            &*typed_notebook.alloc_init_t(5).expect(line_str!())
+        });
+
+        assert_eq!(5, *frozen.root());
+
+        let typed_notebook = frozen.thaw();
+
+        assert_eq!(6, *typed_notebook.alloc_init_t(6).expect(line_str!()));
+        assert_eq!((), *typed_notebook.freeze().root());
+    }
+}
@@ -1,5 +1,7 @@
 use core::fmt;
 use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+use std::sync::Arc;
 
 use crate::TypedNotebook;
 
@@ -50,8 +52,63 @@ impl<'book, T> Drop for Handle<'book, T> {
         unsafe {
             // cleans up any resources the type owns outside of the notebook
             (self.t as *mut T).drop_in_place();
+            self.notebook.dealloc_t(self.t);
         }
+    }
+}
+
+/// A handle which keeps the notebook alive instead of borrowing it, so it can be stored anywhere
+/// and moved to other threads. The notebook frees its pages once the last owned handle and every
+/// `SharedNotebook` pointing to it are dropped.
+pub struct OwnedHandle<T, N: TypedNotebook<T>> {
+    notebook: Arc<N>,
+    t: NonNull<T>,
+}
+
+impl<T, N: TypedNotebook<T>> OwnedHandle<T, N> {
+    /// The value must have been allocated by the notebook and must not be referenced elsewhere.
+    pub(crate) unsafe fn new(notebook: Arc<N>, t: &mut T) -> OwnedHandle<T, N> {
+        OwnedHandle { notebook, t: NonNull::from(t) }
+    }
+}
+
+impl<T: fmt::Debug, N: TypedNotebook<T>> fmt::Debug for OwnedHandle<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OwnedHandle").field("t", &**self).finish()
+    }
+}
 
-        self.notebook.dealloc_t(self.t);
+impl<T: fmt::Display, N: TypedNotebook<T>> fmt::Display for OwnedHandle<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
     }
 }
+
+impl<T, N: TypedNotebook<T>> Deref for OwnedHandle<T, N> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { self.t.as_ref() }
+    }
+}
+
+impl<T, N: TypedNotebook<T>> DerefMut for OwnedHandle<T, N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { self.t.as_mut() }
+    }
+}
+
+impl<T, N: TypedNotebook<T>> Drop for OwnedHandle<T, N> {
+    fn drop(&mut self) {
+        unsafe {
+            // cleans up any resources the type owns outside of the notebook
+            self.t.as_ptr().drop_in_place();
+            self.notebook.dealloc_t(self.t.as_ref());
+        }
+    }
+}
+
+// The handle owns the value, and the notebook may be used from the thread dropping the handle.
+unsafe impl<T: Send, N: TypedNotebook<T> + Send + Sync> Send for OwnedHandle<T, N> {}
+
+unsafe impl<T: Sync, N: TypedNotebook<T> + Send + Sync> Sync for OwnedHandle<T, N> {}
@@ -1,21 +1,43 @@
 #![cfg_attr(feature = "allocator_api", feature(allocator_api))]
 
-pub use allocator::StdAllocator;
-pub use handle::Handle;
+pub use allocator::{
+    BookcaseAllocator,
+    BorrowedAllocator,
+    GlobalAllocator,
+    InterchangeableAllocator,
+    NotebookAllocator,
+    StdAllocator,
+};
+pub use checkpoint::Checkpoint;
+pub use error::{Error, NewError};
+pub use frozen::FrozenNotebook;
+pub use handle::{Handle, OwnedHandle};
+pub use local::ThreadLocalNotebook;
+pub use lock::{RawLock, SpinLock, TicketLock};
 pub use notebook::*;
 pub use page::*;
+pub use shared::SharedNotebook;
+pub use sharded::ShardedNotebook;
 pub use strategy::*;
 
 #[cfg(not(test))]
 ::bookcase_alloc_macros::assert_release_channel!();
 
 pub(crate) mod allocator;
+pub(crate) mod bump;
+pub(crate) mod cache;
 pub(crate) mod chapter;
+pub(crate) mod checkpoint;
 pub(crate) mod error;
+pub(crate) mod frozen;
 pub(crate) mod handle;
+pub(crate) mod local;
+pub(crate) mod lock;
 pub(crate) mod notebook;
 pub(crate) mod page;
 pub(crate) mod seal;
+pub(crate) mod shared;
+pub(crate) mod sharded;
 pub(crate) mod strategy;
 #[cfg(test)]
 pub(crate) mod test;
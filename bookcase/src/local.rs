@@ -0,0 +1,242 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::mem::{self, ManuallyDrop};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+
+use crate::allocator::BookcaseAllocator;
+use crate::notebook::PersonalMultiNotebook;
+use crate::page::Utensil;
+use crate::strategy::{GrowthStrategy, SizeStrategy};
+
+/// Every owner gets its own id, so threads can tell the notebooks of different owners apart.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+type Finished<A, U> = Mutex<Vec<PersonalMultiNotebook<A, U>>>;
+
+/// The notebook of one owner on one thread.
+trait Slot {
+    fn id(&self) -> usize;
+
+    /// Whether the owner was dropped, so the notebook can never be collected.
+    fn orphaned(&self) -> bool;
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+struct Local<A: BookcaseAllocator, U: Utensil> {
+    id: usize,
+    /// Only taken when the slot is dropped.
+    notebook: ManuallyDrop<PersonalMultiNotebook<A, U>>,
+    finished: Weak<Finished<A, U>>,
+}
+
+impl<A: BookcaseAllocator + 'static, U: Utensil + 'static> Slot for Local<A, U> {
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn orphaned(&self) -> bool {
+        self.finished.strong_count() == 0
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl<A: BookcaseAllocator, U: Utensil> Drop for Local<A, U> {
+    /// Hands the notebook to the owner when the thread finishes, or frees it if the owner is gone.
+    fn drop(&mut self) {
+        // the notebook is never touched again
+        let notebook = unsafe { ManuallyDrop::take(&mut self.notebook) };
+
+        if let Some(finished) = self.finished.upgrade() {
+            finished.lock().unwrap_or_else(PoisonError::into_inner).push(notebook);
+        }
+    }
+}
+
+thread_local! {
+    static SLOTS: RefCell<Vec<Box<dyn Slot>>> = RefCell::new(Vec::new());
+}
+
+/// Gives every thread its own personal notebook, created the first time the thread asks for it.
+/// When a thread finishes, its notebook is kept by the owner instead of being dropped, so
+/// everything the thread allocated can be collected. Dropping the owner first is fine too, the
+/// notebooks of threads still running are then dropped when those threads finish.
+pub struct ThreadLocalNotebook<A: BookcaseAllocator, U: Utensil> {
+    id: usize,
+    allocator: A,
+    size: SizeStrategy,
+    growth: GrowthStrategy,
+    finished: Arc<Finished<A, U>>,
+}
+
+impl<A, U> ThreadLocalNotebook<A, U>
+where
+    A: BookcaseAllocator + Clone + 'static,
+    U: Utensil + 'static,
+{
+    /// Every thread gets its own clone of the allocator.
+    pub fn new(
+        allocator: A,
+        size: SizeStrategy,
+        growth: GrowthStrategy,
+    ) -> ThreadLocalNotebook<A, U> {
+        ThreadLocalNotebook {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            allocator,
+            size,
+            growth,
+            finished: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Runs the closure with the notebook of the current thread. A thread that is being torn down
+    /// gets a new notebook which is kept by the owner after the closure returns.
+    pub fn with<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&PersonalMultiNotebook<A, U>) -> R,
+    {
+        match SLOTS.try_with(|slots| self.local_impl(&mut slots.borrow_mut())) {
+            // The slot is boxed, so the notebook stays in place while other slots are added.
+            // Only `collect` removes the slot of a living owner, which needs exclusive access.
+            Ok(notebook) => f(unsafe { &*notebook }),
+            Err(_) => {
+                let notebook = self.new_impl();
+                let r = f(&notebook);
+
+                self.finished.lock().unwrap_or_else(PoisonError::into_inner).push(notebook);
+                r
+            }
+        }
+    }
+
+    /// Takes the notebooks of every finished thread and of the current thread. Notebooks of
+    /// threads still running stay with those threads. The notebooks can be merged with
+    /// `PersonalMultiNotebook::combine`, or with `combine_unchecked` when the allocator is not an
+    /// `InterchangeableAllocator` but its clones can still free each other's memory.
+    pub fn collect(&mut self) -> Vec<PersonalMultiNotebook<A, U>> {
+        let current = SLOTS
+            .try_with(|slots| {
+                let mut slots = slots.borrow_mut();
+                let idx = slots.iter().position(|slot| slot.id() == self.id)?;
+
+                Some(slots.remove(idx))
+            })
+            .ok()
+            .flatten();
+
+        // dropping the slot hands its notebook to the owner
+        drop(current);
+
+        mem::take(&mut *self.finished.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    fn new_impl(&self) -> PersonalMultiNotebook<A, U> {
+        PersonalMultiNotebook::new(self.allocator.clone(), self.size, self.growth)
+    }
+
+    fn local_impl(&self, slots: &mut Vec<Box<dyn Slot>>) -> *const PersonalMultiNotebook<A, U> {
+        let found = slots
+            .iter()
+            .filter(|slot| slot.id() == self.id)
+            .find_map(|slot| slot.as_any().downcast_ref::<Local<A, U>>());
+
+        if let Some(local) = found {
+            return &*local.notebook;
+        }
+
+        // nobody can collect the notebooks of dropped owners, so they are freed early
+        slots.retain(|slot| !slot.orphaned());
+
+        let local = Box::new(Local {
+            id: self.id,
+            notebook: ManuallyDrop::new(self.new_impl()),
+            finished: Arc::downgrade(&self.finished),
+        });
+        let notebook = &*local.notebook as *const PersonalMultiNotebook<A, U>;
+
+        slots.push(local);
+        notebook
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+
+    use crate::*;
+
+    fn new_local() -> ThreadLocalNotebook<StdAllocator, Pen> {
+        ThreadLocalNotebook::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(8),
+            GrowthStrategy::Constant,
+        )
+    }
+
+    #[test]
+    fn test_same_notebook_on_same_thread() {
+        let mut notebook = new_local();
+
+        let first = notebook.with(|n| n.alloc_init(1u64).expect(line_str!()) as *mut u64 as usize);
+        let second = notebook.with(|inner| {
+            // nested calls see the same notebook
+            notebook.with(|n| assert_eq!(inner as *const _, n as *const _));
+            inner.alloc_init(2u64).expect(line_str!()) as *mut u64 as usize
+        });
+
+        assert_eq!(first + 8, second);
+        assert_eq!(1, notebook.collect().len());
+        assert!(notebook.collect().is_empty());
+    }
+
+    #[test]
+    fn test_finished_threads_are_collected() {
+        let notebook = Arc::new(new_local());
+
+        let addrs: Vec<(u64, usize)> = (0..4u64)
+            .map(|t| {
+                let notebook = notebook.clone();
+
+                thread::spawn(move || {
+                    notebook.with(|n| (t, n.alloc_init(t).expect(line_str!()) as *mut u64 as usize))
+                })
+            })
+            .map(|worker| worker.join().expect(line_str!()))
+            .collect();
+
+        let mut notebook = Arc::try_unwrap(notebook).map_err(|_| ()).expect(line_str!());
+        let notebooks = notebook.collect();
+
+        assert_eq!(4, notebooks.len());
+
+        // the values live as long as the collected notebooks
+        for (t, addr) in addrs {
+            assert_eq!(t, unsafe { *(addr as *const u64) });
+        }
+    }
+
+    #[test]
+    fn test_owner_dropped_before_threads_finish() {
+        let notebook = Arc::new(new_local());
+        let (sender, receiver) = std::sync::mpsc::channel::<()>();
+
+        let worker = {
+            let notebook = notebook.clone();
+
+            thread::spawn(move || {
+                notebook.with(|n| *n.alloc_init(1u64).expect(line_str!()));
+                drop(notebook);
+                receiver.recv().expect(line_str!());
+            })
+        };
+
+        drop(notebook);
+        sender.send(()).expect(line_str!());
+        worker.join().expect(line_str!());
+    }
+}
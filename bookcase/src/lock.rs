@@ -0,0 +1,273 @@
+use std::hint::spin_loop;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::thread::{panicking, yield_now};
+
+use crate::error::Error;
+
+/// Guards the pages of a public notebook. Implement this to plug in another lock.
+///
+/// # Safety
+///
+/// At most one closure may run inside `locked` at a time across every thread. A closure that
+/// panicked may have left the notebook half written, so later calls should fail with
+/// `Error::PoisonError` instead of running.
+pub unsafe trait RawLock: Default + Send + Sync {
+    /// Runs the closure while holding the lock.
+    fn locked<R, F: FnOnce() -> R>(&self, f: F) -> Result<R, Error>;
+}
+
+unsafe impl RawLock for RwLock<()> {
+    #[inline(always)]
+    fn locked<R, F: FnOnce() -> R>(&self, f: F) -> Result<R, Error> {
+        let _guard = self.write().map_err(|_| Error::PoisonError)?;
+
+        Ok(f())
+    }
+}
+
+unsafe impl RawLock for Mutex<()> {
+    #[inline(always)]
+    fn locked<R, F: FnOnce() -> R>(&self, f: F) -> Result<R, Error> {
+        let _guard = self.lock().map_err(|_| Error::PoisonError)?;
+
+        Ok(f())
+    }
+}
+
+/// Spins until the lock is free. Cheapest when the lock is only held for a moment, which is the
+/// case for notebooks that bump allocate.
+#[derive(Debug, Default)]
+pub struct SpinLock {
+    held: AtomicBool,
+    poisoned: AtomicBool,
+}
+
+unsafe impl RawLock for SpinLock {
+    #[inline(always)]
+    fn locked<R, F: FnOnce() -> R>(&self, f: F) -> Result<R, Error> {
+        let mut spins = 0;
+
+        while self
+            .held
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            relax(&mut spins);
+        }
+
+        let _guard = Unlock {
+            poisoned: &self.poisoned,
+            panicking: panicking(),
+            release: || self.held.store(false, Ordering::Release),
+        };
+
+        if self.poisoned.load(Ordering::Relaxed) {
+            return Err(Error::PoisonError);
+        }
+
+        Ok(f())
+    }
+}
+
+/// Spins until it is the turn of this thread. Threads get the lock in the order they asked for
+/// it, so no thread waits forever under contention.
+#[derive(Debug, Default)]
+pub struct TicketLock {
+    next: AtomicUsize,
+    serving: AtomicUsize,
+    poisoned: AtomicBool,
+}
+
+unsafe impl RawLock for TicketLock {
+    #[inline(always)]
+    fn locked<R, F: FnOnce() -> R>(&self, f: F) -> Result<R, Error> {
+        let ticket = self.next.fetch_add(1, Ordering::Relaxed);
+        let mut spins = 0;
+
+        while self.serving.load(Ordering::Acquire) != ticket {
+            relax(&mut spins);
+        }
+
+        let _guard = Unlock {
+            poisoned: &self.poisoned,
+            panicking: panicking(),
+            release: || self.serving.store(ticket.wrapping_add(1), Ordering::Release),
+        };
+
+        if self.poisoned.load(Ordering::Relaxed) {
+            return Err(Error::PoisonError);
+        }
+
+        Ok(f())
+    }
+}
+
+/// Spins for a while, then gives the rest of the time slice to the holder of the lock in case it
+/// was preempted.
+#[inline(always)]
+fn relax(spins: &mut u32) {
+    if *spins < 64 {
+        *spins += 1;
+        spin_loop();
+    } else {
+        yield_now();
+    }
+}
+
+/// Releases a lock even when the closure panics, and poisons it if so.
+struct Unlock<'a, F: FnMut()> {
+    poisoned: &'a AtomicBool,
+    /// Locks taken by destructors while the thread unwinds from another panic are not poisoned.
+    panicking: bool,
+    release: F,
+}
+
+impl<'a, F: FnMut()> Drop for Unlock<'a, F> {
+    fn drop(&mut self) {
+        if !self.panicking && panicking() {
+            self.poisoned.store(true, Ordering::Relaxed);
+        }
+
+        (self.release)();
+    }
+}
+
+/// How a notebook guards its pages. Personal notebooks are never shared so they skip locking.
+pub trait Locking {
+    /// Whether several threads may allocate at the same time.
+    const SHARED: bool;
+
+    fn locked<R, F: FnOnce() -> R>(&self, f: F) -> Result<R, Error>;
+}
+
+impl Locking for () {
+    const SHARED: bool = false;
+
+    #[inline(always)]
+    fn locked<R, F: FnOnce() -> R>(&self, f: F) -> Result<R, Error> {
+        Ok(f())
+    }
+}
+
+impl<L: RawLock> Locking for L {
+    const SHARED: bool = true;
+
+    #[inline(always)]
+    fn locked<R, F: FnOnce() -> R>(&self, f: F) -> Result<R, Error> {
+        RawLock::locked(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex, RwLock};
+    use std::thread;
+
+    use crate::*;
+
+    fn assert_poisons<L: RawLock>() {
+        let lock = L::default();
+
+        assert_eq!(1, lock.locked(|| 1).expect(line_str!()));
+        assert!(catch_unwind(AssertUnwindSafe(|| lock.locked(|| panic!()))).is_err());
+        assert!(matches!(lock.locked(|| 1), Err(Error::PoisonError)));
+    }
+
+    #[test]
+    fn test_locks_are_poisoned_by_panics() {
+        assert_poisons::<SpinLock>();
+        assert_poisons::<TicketLock>();
+        assert_poisons::<Mutex<()>>();
+        assert_poisons::<RwLock<()>>();
+    }
+
+    fn assert_unwinding_does_not_poison<L: RawLock>() {
+        // the eraser takes the lock for every allocation
+        let notebook = PublicMultiNotebook::<_, Eraser, L>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(8),
+            GrowthStrategy::Constant,
+        );
+
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            // the handle takes the lock to free the value while the panic unwinds
+            let _handle = notebook.new(String::from("unwinding")).expect(line_str!());
+
+            panic!();
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(1, *notebook.alloc_init(1u64).expect(line_str!()));
+    }
+
+    #[test]
+    fn test_locks_taken_while_unwinding_are_not_poisoned() {
+        assert_unwinding_does_not_poison::<SpinLock>();
+        assert_unwinding_does_not_poison::<TicketLock>();
+        assert_unwinding_does_not_poison::<Mutex<()>>();
+        assert_unwinding_does_not_poison::<RwLock<()>>();
+    }
+
+    fn assert_counts<L: RawLock + 'static>() {
+        let lock = Arc::new(L::default());
+        let count = Arc::new(AtomicUsize::new(0));
+
+        let workers: Vec<_> = (0..8)
+            .map(|_| {
+                let lock = lock.clone();
+                let count = count.clone();
+
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        lock.locked(|| {
+                            // a racy increment only adds up when the lock excludes everyone else
+                            let n = count.load(Ordering::Relaxed);
+                            count.store(n + 1, Ordering::Relaxed);
+                        }).expect(line_str!());
+                    }
+                })
+            })
+            .collect();
+
+        for worker in workers {
+            worker.join().expect(line_str!());
+        }
+
+        assert_eq!(8000, count.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_locks_exclude_each_other() {
+        assert_counts::<SpinLock>();
+        assert_counts::<TicketLock>();
+        assert_counts::<Mutex<()>>();
+    }
+
+    #[test]
+    fn test_notebook_with_ticket_lock() {
+        let notebook = Arc::new(PublicMultiNotebook::<_, Pen, TicketLock>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(8),
+            GrowthStrategy::Constant,
+        ));
+
+        let workers: Vec<_> = (0..4u64)
+            .map(|t| {
+                let notebook = notebook.clone();
+
+                thread::spawn(move || {
+                    (0..100u64)
+                        .map(|idx| *notebook.alloc_init(t * 1000 + idx).expect(line_str!()))
+                        .sum::<u64>()
+                })
+            })
+            .collect();
+
+        let sum: u64 = workers.into_iter().map(|w| w.join().expect(line_str!())).sum();
+
+        assert_eq!((0..4u64).map(|t| t * 100_000 + 4950).sum::<u64>(), sum);
+    }
+}
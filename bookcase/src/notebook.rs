@@ -1,109 +1,394 @@
+use std::alloc::Layout;
 use std::cell::RefCell;
 use std::marker::PhantomData;
-use std::mem::{align_of, size_of};
+use std::mem::{ManuallyDrop, MaybeUninit};
+use std::ptr::{self, copy_nonoverlapping, slice_from_raw_parts_mut, NonNull};
+use std::str::from_utf8_unchecked_mut;
 use std::sync::RwLock;
 
-use crate::{GrowthStrategy, SizeStrategy};
-use crate::allocator::BookcaseAllocator;
+use crate::{GrowthStrategy, SizeStrategy, MAX_PAGE_BYTES};
+use crate::allocator::{BookcaseAllocator, BorrowedAllocator, InterchangeableAllocator};
+use crate::bump::Bump;
+use crate::cache::ThreadCache;
 use crate::chapter::Chapter;
+use crate::checkpoint::Checkpoint;
+use crate::error::{Error, NewError};
+use crate::frozen::FrozenNotebook;
 use crate::handle::Handle;
+use crate::lock::{Locking, RawLock};
 use crate::page::Utensil;
 use crate::seal::Sealed;
 
 pub trait Notebook: Sealed {
-    fn alloc<T: Copy>(&self) -> Option<&mut T>;
+    /// Allocates raw memory fitting the layout. This is what allows a notebook to back other
+    /// allocators.
+    fn try_alloc_layout(&self, layout: Layout) -> Result<NonNull<u8>, Error>;
+
+    /// Gives memory allocated with the layout back to the notebook. Whether the memory can be
+    /// reused depends on the utensil.
+    ///
+    /// # Safety
+    ///
+    /// The memory must have been allocated by this notebook with the same layout and must not be
+    /// used after this call.
+    unsafe fn dealloc_layout(&self, ptr: NonNull<u8>, layout: Layout) -> bool;
+
+    #[inline(always)]
+    fn try_alloc<T: Copy>(&self) -> Result<&mut T, Error> {
+        self.try_alloc_layout(Layout::new::<T>()).map(|ptr| unsafe { &mut *ptr.as_ptr().cast() })
+    }
+
+    #[inline(always)]
+    fn alloc<T: Copy>(&self) -> Option<&mut T> {
+        self.try_alloc().ok()
+    }
 
     /// Zeroes all bytes allocated including padding.
     #[inline(always)]
-    fn alloc_zero<T: Copy>(&self) -> Option<&mut T> {
-        let t_ref = self.alloc()?;
+    fn try_alloc_zero<T: Copy>(&self) -> Result<&mut T, Error> {
+        let t_ref = self.try_alloc()?;
 
         unsafe {
             (t_ref as *mut T).write_bytes(0, 1);
         }
 
-        Some(t_ref)
+        Ok(t_ref)
+    }
+
+    /// Zeroes all bytes allocated including padding.
+    #[inline(always)]
+    fn alloc_zero<T: Copy>(&self) -> Option<&mut T> {
+        self.try_alloc_zero().ok()
+    }
+
+    /// Initializes the memory with the given value.
+    #[inline(always)]
+    fn try_alloc_init<T: Copy>(&self, t: T) -> Result<&mut T, Error> {
+        Ok(self.try_alloc_uninit()?.write(t))
     }
 
     /// Initializes the memory with the given value.
     #[inline(always)]
     fn alloc_init<T: Copy>(&self, t: T) -> Option<&mut T> {
-        let t_ref = self.alloc()?;
+        self.try_alloc_init(t).ok()
+    }
+
+    /// Leaves the memory uninitialized for the caller to write.
+    #[inline(always)]
+    fn try_alloc_uninit<T>(&self) -> Result<&mut MaybeUninit<T>, Error> {
+        self.try_alloc_layout(Layout::new::<T>()).map(|ptr| unsafe { &mut *ptr.as_ptr().cast() })
+    }
+
+    /// Leaves the memory uninitialized for the caller to write.
+    #[inline(always)]
+    fn alloc_uninit<T>(&self) -> Option<&mut MaybeUninit<T>> {
+        self.try_alloc_uninit().ok()
+    }
+
+    /// Initializes the memory with the value returned by the closure. The closure is called after
+    /// the memory is allocated, so it can build the value in place and may use the notebook. The
+    /// notebook never drops the value, use `new` to get a handle which drops it.
+    #[inline(always)]
+    fn try_alloc_with<T, F: FnOnce() -> T>(&self, f: F) -> Result<&mut T, Error> {
+        Ok(self.try_alloc_uninit()?.write(f()))
+    }
+
+    /// Initializes the memory with the value returned by the closure. The closure is called after
+    /// the memory is allocated, so it can build the value in place and may use the notebook. The
+    /// notebook never drops the value, use `new` to get a handle which drops it.
+    #[inline(always)]
+    fn alloc_with<T, F: FnOnce() -> T>(&self, f: F) -> Option<&mut T> {
+        self.try_alloc_with(f).ok()
+    }
+
+    /// Leaves the memory of every element uninitialized for the caller to write.
+    #[inline(always)]
+    fn try_alloc_slice_uninit<T>(&self, len: usize) -> Result<&mut [MaybeUninit<T>], Error> {
+        let ptr = self.try_alloc_layout(Layout::array::<T>(len)?)?;
+
+        unsafe {
+            Ok(&mut *slice_from_raw_parts_mut(ptr.as_ptr().cast(), len))
+        }
+    }
+
+    /// Leaves the memory of every element uninitialized for the caller to write.
+    #[inline(always)]
+    fn alloc_slice_uninit<T>(&self, len: usize) -> Option<&mut [MaybeUninit<T>]> {
+        self.try_alloc_slice_uninit(len).ok()
+    }
+
+    /// Copies every element of the slice into the notebook.
+    #[inline(always)]
+    fn try_alloc_slice_copy<T: Copy>(&self, src: &[T]) -> Result<&mut [T], Error> {
+        let slice = self.try_alloc_slice_uninit::<T>(src.len())?;
+
+        unsafe {
+            copy_nonoverlapping(src.as_ptr(), slice.as_mut_ptr().cast(), src.len());
+            Ok(&mut *(slice as *mut [MaybeUninit<T>] as *mut [T]))
+        }
+    }
+
+    /// Copies every element of the slice into the notebook.
+    #[inline(always)]
+    fn alloc_slice_copy<T: Copy>(&self, src: &[T]) -> Option<&mut [T]> {
+        self.try_alloc_slice_copy(src).ok()
+    }
+
+    /// Initializes each element with the value the closure returns for its index. The notebook
+    /// never drops the elements.
+    #[inline(always)]
+    fn try_alloc_slice_fill_with<T, F: FnMut(usize) -> T>(
+        &self,
+        len: usize,
+        mut f: F,
+    ) -> Result<&mut [T], Error> {
+        let slice = self.try_alloc_slice_uninit::<T>(len)?;
+
+        for (idx, t_ref) in slice.iter_mut().enumerate() {
+            t_ref.write(f(idx));
+        }
+
+        unsafe {
+            Ok(&mut *(slice as *mut [MaybeUninit<T>] as *mut [T]))
+        }
+    }
+
+    /// Initializes each element with the value the closure returns for its index. The notebook
+    /// never drops the elements.
+    #[inline(always)]
+    fn alloc_slice_fill_with<T, F: FnMut(usize) -> T>(
+        &self,
+        len: usize,
+        f: F,
+    ) -> Option<&mut [T]> {
+        self.try_alloc_slice_fill_with(len, f).ok()
+    }
+
+    /// Allocates room for the reported length up front, then moves the elements in. If the
+    /// iterator ends early, the returned slice only covers the elements it produced. The notebook
+    /// never drops the elements.
+    #[inline(always)]
+    fn try_alloc_slice_from_iter<T, I>(&self, iter: I) -> Result<&mut [T], Error>
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let iter = iter.into_iter();
+        let slice = self.try_alloc_slice_uninit::<T>(iter.len())?;
+        let mut len = 0;
+
+        for (t_ref, t) in slice.iter_mut().zip(iter) {
+            t_ref.write(t);
+            len += 1;
+        }
+
+        unsafe {
+            Ok(&mut *slice_from_raw_parts_mut(slice.as_mut_ptr().cast(), len))
+        }
+    }
 
-        *t_ref = t;
-        Some(t_ref)
+    /// Allocates room for the reported length up front, then moves the elements in. If the
+    /// iterator ends early, the returned slice only covers the elements it produced. The notebook
+    /// never drops the elements.
+    #[inline(always)]
+    fn alloc_slice_from_iter<T, I>(&self, iter: I) -> Option<&mut [T]>
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        self.try_alloc_slice_from_iter(iter).ok()
+    }
+
+    /// Copies the string into the notebook.
+    #[inline(always)]
+    fn try_alloc_str(&self, src: &str) -> Result<&mut str, Error> {
+        let bytes = self.try_alloc_slice_copy(src.as_bytes())?;
+
+        // the bytes were copied from a str, so they are valid utf-8
+        unsafe {
+            Ok(from_utf8_unchecked_mut(bytes))
+        }
+    }
+
+    /// Copies the string into the notebook.
+    #[inline(always)]
+    fn alloc_str(&self, src: &str) -> Option<&mut str> {
+        self.try_alloc_str(src).ok()
+    }
+
+    /// Moves a handle to the caller which will call drop on the value when the handle is dropped.
+    /// The value is handed back in the error if it could not be moved into the notebook.
+    #[inline(always)]
+    fn try_new<T>(&self, t: T) -> Result<Handle<T>, NewError<T>> where Self: Sized {
+        handle(self, self.try_alloc_uninit(), t)
     }
 
     /// Moves a handle to the caller which will call drop on the value when the handle is dropped.
-    fn new<T>(&self, t: T) -> Option<Handle<T>> where Self: Sized;
+    #[inline(always)]
+    fn new<T>(&self, t: T) -> Option<Handle<T>> where Self: Sized {
+        self.try_new(t).ok()
+    }
 
-    fn dealloc<T>(&self, t: &T) -> bool;
+    /// Gives the memory of the value back to the notebook. Whether the memory can be reused
+    /// depends on the utensil.
+    ///
+    /// # Safety
+    ///
+    /// The value must have been allocated by this notebook and must not be used after this call.
+    #[inline(always)]
+    unsafe fn dealloc<T>(&self, t: &T) -> bool {
+        self.dealloc_layout(NonNull::from(t).cast(), Layout::new::<T>())
+    }
 }
 
 /// *_t suffix is used so as not to clash with Notebook's interface.
 pub trait TypedNotebook<T>: Sealed {
-    fn alloc_t(&self) -> Option<&mut T> where T: Copy;
+    /// Leaves the memory uninitialized for the caller to write.
+    fn try_alloc_uninit_t(&self) -> Result<&mut MaybeUninit<T>, Error>;
+
+    /// Leaves the memory uninitialized for the caller to write.
+    #[inline(always)]
+    fn alloc_uninit_t(&self) -> Option<&mut MaybeUninit<T>> {
+        self.try_alloc_uninit_t().ok()
+    }
+
+    #[inline(always)]
+    fn try_alloc_t(&self) -> Result<&mut T, Error> where T: Copy {
+        self.try_alloc_uninit_t().map(|t_ref| unsafe { &mut *t_ref.as_mut_ptr() })
+    }
+
+    #[inline(always)]
+    fn alloc_t(&self) -> Option<&mut T> where T: Copy {
+        self.try_alloc_t().ok()
+    }
 
     /// Zeroes all bytes allocated including padding.
     #[inline(always)]
-    fn alloc_zero_t(&self) -> Option<&mut T> where T: Copy {
-        let t_ref = self.alloc_t()?;
+    fn try_alloc_zero_t(&self) -> Result<&mut T, Error> where T: Copy {
+        let t_ref = self.try_alloc_t()?;
 
         unsafe {
             (t_ref as *mut T).write_bytes(0, 1);
         }
 
-        Some(t_ref)
+        Ok(t_ref)
+    }
+
+    /// Zeroes all bytes allocated including padding.
+    #[inline(always)]
+    fn alloc_zero_t(&self) -> Option<&mut T> where T: Copy {
+        self.try_alloc_zero_t().ok()
+    }
+
+    /// Initializes the memory with the given value.
+    #[inline(always)]
+    fn try_alloc_init_t(&self, t: T) -> Result<&mut T, Error> where T: Copy {
+        Ok(self.try_alloc_uninit_t()?.write(t))
     }
 
     /// Initializes the memory with the given value.
     #[inline(always)]
     fn alloc_init_t(&self, t: T) -> Option<&mut T> where T: Copy {
-        let t_ref = self.alloc_t()?;
+        self.try_alloc_init_t(t).ok()
+    }
 
-        *t_ref = t;
-        Some(t_ref)
+    /// Initializes the memory with the value returned by the closure. The closure is called after
+    /// the memory is allocated, so it can build the value in place and may use the notebook. The
+    /// notebook never drops the value, use `new_t` to get a handle which drops it.
+    #[inline(always)]
+    fn try_alloc_with_t<F: FnOnce() -> T>(&self, f: F) -> Result<&mut T, Error>
+    where
+        Self: Sized,
+    {
+        Ok(self.try_alloc_uninit_t()?.write(f()))
+    }
+
+    /// Initializes the memory with the value returned by the closure. The closure is called after
+    /// the memory is allocated, so it can build the value in place and may use the notebook. The
+    /// notebook never drops the value, use `new_t` to get a handle which drops it.
+    #[inline(always)]
+    fn alloc_with_t<F: FnOnce() -> T>(&self, f: F) -> Option<&mut T>
+    where
+        Self: Sized,
+    {
+        self.try_alloc_with_t(f).ok()
+    }
+
+    /// Moves a handle to the caller which will call drop on the value when the handle is dropped.
+    /// The value is handed back in the error if it could not be moved into the notebook.
+    #[inline(always)]
+    fn try_new_t(&self, t: T) -> Result<Handle<T>, NewError<T>> where Self: Sized {
+        handle(self, self.try_alloc_uninit_t(), t)
     }
 
     /// Moves a handle to the caller which will call drop on the value when the handle is dropped.
-    fn new_t(&self, t: T) -> Option<Handle<T>> where Self: Sized;
+    #[inline(always)]
+    fn new_t(&self, t: T) -> Option<Handle<T>> where Self: Sized {
+        self.try_new_t(t).ok()
+    }
 
-    fn dealloc_t(&self, t: &T) -> bool;
+    /// Gives the memory of the value back to the notebook. Whether the memory can be reused
+    /// depends on the utensil.
+    ///
+    /// # Safety
+    ///
+    /// The value must have been allocated by this notebook and must not be used after this call.
+    unsafe fn dealloc_t(&self, t: &T) -> bool;
 }
 
 /// Allows Notebooks to be used as TypedNotebooks.
 impl<N: Notebook, T> TypedNotebook<T> for N {
     #[inline(always)]
-    fn alloc_t(&self) -> Option<&mut T> where T: Copy {
-        self.alloc::<T>()
+    fn try_alloc_uninit_t(&self) -> Result<&mut MaybeUninit<T>, Error> {
+        self.try_alloc_uninit::<T>()
     }
 
     #[inline(always)]
-    fn alloc_zero_t(&self) -> Option<&mut T> where T: Copy {
-        self.alloc_zero::<T>()
+    fn try_alloc_t(&self) -> Result<&mut T, Error> where T: Copy {
+        self.try_alloc::<T>()
     }
 
     #[inline(always)]
-    fn alloc_init_t(&self, t: T) -> Option<&mut T> where T: Copy {
-        self.alloc_init::<T>(t)
+    fn try_alloc_zero_t(&self) -> Result<&mut T, Error> where T: Copy {
+        self.try_alloc_zero::<T>()
+    }
+
+    #[inline(always)]
+    fn try_alloc_init_t(&self, t: T) -> Result<&mut T, Error> where T: Copy {
+        self.try_alloc_init::<T>(t)
+    }
+
+    #[inline(always)]
+    fn try_alloc_with_t<F: FnOnce() -> T>(&self, f: F) -> Result<&mut T, Error>
+    where
+        Self: Sized,
+    {
+        self.try_alloc_with::<T, F>(f)
     }
 
     #[inline(always)]
-    fn new_t(&self, t: T) -> Option<Handle<T>> {
-        self.new::<T>(t)
+    fn try_new_t(&self, t: T) -> Result<Handle<T>, NewError<T>> {
+        self.try_new::<T>(t)
     }
 
     #[inline(always)]
-    fn dealloc_t(&self, t: &T) -> bool {
+    unsafe fn dealloc_t(&self, t: &T) -> bool {
         self.dealloc::<T>(t)
     }
 }
 
 const NUM_ALIGNS: usize = 5;
 
-impl Sealed for () {}
-
-impl Sealed for RwLock<()> {}
+/// A saved point in a notebook. Rewinding the notebook to the marker erases everything written
+/// after the marker was taken while keeping the pages for reuse. Rewinding requires exclusive
+/// access to the notebook, so nothing allocated after the marker can still be referenced.
+///
+/// Rewinding with a marker taken from a different notebook will never erase less than the
+/// notebook's own marker would, but it may erase more.
+#[derive(Clone, Debug)]
+pub struct Marker {
+    chapters: Vec<Vec<usize>>,
+}
 
 /// Can allocate any type. All types will be allocated to their proper alignment. This is
 /// especially useful for processing heterogeneous granular data like parsing a JSON string
@@ -112,339 +397,863 @@ pub struct MultiNotebook<A: BookcaseAllocator, U: Utensil, L = ()> {
     allocator: A,
     size: SizeStrategy,
     growth: GrowthStrategy,
-    chapters: RefCell<[Chapter<U>; NUM_ALIGNS]>,
-    lock: L,
+    max_page_bytes: usize,
+    chapters: [RefCell<Chapter<U>>; NUM_ALIGNS],
+    bumps: [Bump; NUM_ALIGNS],
+    cache: ThreadCache,
+    /// Every chapter has its own lock, so values of different alignments are allocated in
+    /// parallel.
+    locks: [L; NUM_ALIGNS],
 }
 
-impl<A: BookcaseAllocator, U: Utensil, L: Sealed> Sealed for MultiNotebook<A, U, L> {}
+impl<A: BookcaseAllocator, U: Utensil, L: Locking> Sealed for MultiNotebook<A, U, L> {}
 
 // These implementations are to reduce duplication.
 impl<A: BookcaseAllocator, U: Utensil, L> MultiNotebook<A, U, L> {
     #[cfg(test)]
-    pub(crate) fn clone_chapters_impl(&self) -> [Vec<Vec<u8>>; NUM_ALIGNS] {
-        [
-            self.chapters.borrow().get(0).unwrap().pages().iter().map(|p: &crate::page::Page<U>| {
-                p.clone_buffer()
-            }).collect(),
-            self.chapters.borrow().get(1).unwrap().pages().iter().map(|p: &crate::page::Page<U>| {
-                p.clone_buffer()
-            }).collect(),
-            self.chapters.borrow().get(2).unwrap().pages().iter().map(|p: &crate::page::Page<U>| {
-                p.clone_buffer()
-            }).collect(),
-            self.chapters.borrow().get(3).unwrap().pages().iter().map(|p: &crate::page::Page<U>| {
-                p.clone_buffer()
-            }).collect(),
-            self.chapters.borrow().get(4).unwrap().pages().iter().map(|p: &crate::page::Page<U>| {
-                p.clone_buffer()
-            }).collect(),
-        ]
+    pub(crate) fn clone_chapter_impl(&self, idx: usize) -> Vec<Vec<u8>> {
+        self.chapters[idx].borrow().pages().iter().map(|p| p.clone_buffer()).collect()
     }
 
     #[inline(always)]
-    fn to_string_impl(&self) -> String {
-        self.chapters
-            .borrow()
-            .iter()
-            .enumerate()
-            .map(|(idx, c)| format!("ch{}:{}", idx + 1, c.to_string()))
-            .collect::<Vec<String>>()
-            .join("\n")
+    fn to_string_impl(&self, idx: usize) -> String {
+        format!("ch{}:{}", idx + 1, self.chapters[idx].borrow().to_string())
     }
 
     #[inline(always)]
-    fn alloc_impl<T>(&self) -> Option<&mut T> {
-        let t_size = size_of::<T>();
-        let t_align = align_of::<T>();
-        let base_bytes = self.size.base_bytes(t_size, t_align);
-        let mut chapters = self.chapters.borrow_mut();
-        let chapter = chapters.get_mut(chapter_idx(t_align))?;
-        let page_bytes = self.growth.page_bytes(base_bytes, chapter.pages().len());
-        let t = chapter.alloc(&self.allocator, t_size, t_align, page_bytes)?.cast();
-
-        unsafe {
-            Some(&mut *t)
+    fn page_bytes_impl(&self, layout: Layout, chapter: &Chapter<U>) -> Result<usize, Error> {
+        if layout.size() > self.max_page_bytes {
+            return Err(Error::OversizedError(layout.size()));
         }
+
+        let base_bytes = self.size.base_bytes(layout.size(), layout.align());
+
+        Ok(self.growth.page_bytes(base_bytes, chapter.pages().len(), self.max_page_bytes))
     }
 
     #[inline(always)]
-    fn dealloc_impl<T>(&self, t: &T) -> bool {
-        self.chapters.borrow_mut()[chapter_idx(align_of::<T>())].dealloc((t as *const T).cast())
-    }
-}
+    fn alloc_impl(&self, layout: Layout) -> Result<NonNull<u8>, Error> {
+        let mut chapter = self.chapters[chapter_idx(layout.align())].borrow_mut();
+        let page_bytes = self.page_bytes_impl(layout, &chapter)?;
+        let ptr = chapter.alloc(&self.allocator, layout, page_bytes)?;
 
-impl<A: BookcaseAllocator, U: Utensil, L> Drop for MultiNotebook<A, U, L> {
-    fn drop(&mut self) {
-        // Locking is unnecessary since dropping only happens
-        // after all references are no longer held.
-        for chapter in self.chapters.borrow_mut().iter_mut() {
-            chapter.destroy(&self.allocator);
+        // pages are never at the null address
+        unsafe {
+            Ok(NonNull::new_unchecked(ptr))
         }
     }
-}
 
-pub type PersonalMultiNotebook<A, U> = MultiNotebook<A, U, ()>;
+    /// The locked path of lock-free allocation.
+    #[inline(always)]
+    fn bump_impl(&self, layout: Layout) -> Result<NonNull<u8>, Error> {
+        let idx = chapter_idx(layout.align());
+        let mut chapter = self.chapters[idx].borrow_mut();
+        let page_bytes = self.page_bytes_impl(layout, &chapter)?;
 
-impl<A: BookcaseAllocator, U: Utensil> PersonalMultiNotebook<A, U> {
-    pub fn new(
-        allocator: A,
-        size: SizeStrategy,
-        growth: GrowthStrategy,
-    ) -> PersonalMultiNotebook<A, U> {
-        PersonalMultiNotebook {
-            allocator,
-            size,
-            growth,
-            chapters: RefCell::new([
-                Chapter::new(),
-                Chapter::new(),
-                Chapter::new(),
-                Chapter::new(),
-                Chapter::new(),
-            ]),
-            lock: (),
-        }
+        self.bumps[idx].alloc_locked(&mut chapter, &self.allocator, layout, page_bytes)
     }
 
-    #[cfg(test)]
-    pub(crate) fn clone_chapters(&self) -> [Vec<Vec<u8>>; NUM_ALIGNS] {
-        self.clone_chapters_impl()
-    }
-}
+    /// Frees the regions of lock-free allocation, their unused memory is given back to the pages.
+    /// Chunks of every thread are dropped with them.
+    #[inline(always)]
+    fn clear_bumps_impl(&mut self) {
+        self.cache.invalidate();
 
-impl<A: BookcaseAllocator, U: Utensil> ToString for PersonalMultiNotebook<A, U> {
-    fn to_string(&self) -> String {
-        self.to_string_impl()
+        for (chapter, bump) in self.chapters.iter_mut().zip(self.bumps.iter_mut()) {
+            bump.clear(chapter.get_mut());
+        }
     }
-}
 
-impl<A: BookcaseAllocator, U: Utensil> Notebook for PersonalMultiNotebook<A, U> {
     #[inline(always)]
-    fn alloc<T>(&self) -> Option<&mut T> {
-        self.alloc_impl()
+    fn dealloc_impl(&self, ptr: NonNull<u8>, layout: Layout) -> bool {
+        self.chapters[chapter_idx(layout.align())].borrow_mut().dealloc(ptr.as_ptr(), layout)
     }
 
     #[inline(always)]
-    fn new<T>(&self, t: T) -> Option<Handle<T>> where Self: Sized {
-        let t_ref = self.alloc_impl()?;
+    fn mark_impl(&self, idx: usize) -> Vec<usize> {
+        let mut chapter = self.chapters[idx].borrow_mut();
 
-        *t_ref = t;
-        Some(Handle::new(self, t_ref))
+        // memory handed out lock-free after the marker must be erased by it too
+        self.bumps[idx].retire(&mut chapter);
+        chapter.mark()
     }
 
     #[inline(always)]
-    fn dealloc<T>(&self, t: &T) -> bool {
-        self.dealloc_impl(t)
+    fn marker_impl(&self) -> Marker {
+        self.cache.invalidate();
+
+        Marker {
+            chapters: (0..NUM_ALIGNS).map(|idx| self.mark_impl(idx)).collect(),
+        }
     }
-}
 
-pub type PublicMultiNotebook<A, U> = MultiNotebook<A, U, RwLock<()>>;
+    /// Limits how large a page may grow. Values larger than this can't be allocated.
+    pub fn with_max_page_bytes(mut self, max_page_bytes: usize) -> Self {
+        self.max_page_bytes = max_page_bytes.min(MAX_PAGE_BYTES);
+        self
+    }
 
-impl<A: BookcaseAllocator, U: Utensil> PublicMultiNotebook<A, U> {
-    pub fn new(
-        allocator: A,
-        size: SizeStrategy,
-        growth: GrowthStrategy,
-    ) -> PublicMultiNotebook<A, U> {
-        PublicMultiNotebook {
-            lock: RwLock::new(()),
-            allocator,
-            size,
-            growth,
-            chapters: RefCell::new([
-                Chapter::new(),
-                Chapter::new(),
-                Chapter::new(),
-                Chapter::new(),
-                Chapter::new(),
-            ]),
+    /// Gives every thread its own chunk of a page to allocate from, so the notebook is only
+    /// shared when a chunk runs out. Values larger than half a chunk are allocated in the page
+    /// directly. Memory left in a chunk is lost until the notebook is reset or rewound, so chunks
+    /// should be much smaller than a page. Only public notebooks use thread chunks, and not with
+    /// the eraser.
+    pub fn with_thread_cache(mut self, chunk_bytes: usize) -> Self {
+        self.cache = ThreadCache::new().with_chunk_bytes(chunk_bytes);
+        self
+    }
+
+    /// Erases everything while keeping every page, so the notebook can be reused without asking
+    /// the allocator for memory again.
+    pub fn reset(&mut self) {
+        self.clear_bumps_impl();
+
+        for chapter in self.chapters.iter_mut() {
+            chapter.get_mut().reset();
         }
     }
 
-    #[cfg(test)]
-    pub(crate) fn clone_chapters(&self) -> [Vec<Vec<u8>>; NUM_ALIGNS] {
-        let _guard = self.lock.read().unwrap();
+    /// Erases everything while keeping only the largest page of each chapter. The rest of the
+    /// pages are given back to the allocator.
+    pub fn reset_and_trim(&mut self) {
+        self.clear_bumps_impl();
 
-        self.clone_chapters_impl()
+        for chapter in self.chapters.iter_mut() {
+            chapter.get_mut().trim(&self.allocator);
+        }
     }
-}
 
-impl<A: BookcaseAllocator, U: Utensil> ToString for PublicMultiNotebook<A, U> {
-    fn to_string(&self) -> String {
-        let _guard = self.lock.read().unwrap();
+    /// Erases everything written after the marker was taken.
+    pub fn rewind(&mut self, marker: Marker) {
+        self.clear_bumps_impl();
 
-        self.to_string_impl()
+        for (chapter, offsets) in self.chapters.iter_mut().zip(marker.chapters.iter()) {
+            chapter.get_mut().rewind(offsets);
+        }
     }
-}
 
-impl<A: BookcaseAllocator, U: Utensil> Notebook for PublicMultiNotebook<A, U> {
-    #[inline(always)]
-    fn alloc<T>(&self) -> Option<&mut T> {
-        let _guard = self.lock.write().unwrap();
+    /// Saves the current point in the notebook. Everything allocated through the checkpoint can
+    /// be rolled back, which also gives pages created after the checkpoint back to the allocator.
+    pub fn checkpoint(&mut self) -> Checkpoint<'_, Self> {
+        let marker = self.marker_impl();
 
-        self.alloc_impl()
+        Checkpoint::new(self, marker)
     }
 
-    #[inline(always)]
-    fn new<T>(&self, t: T) -> Option<Handle<T>> where Self: Sized {
-        let _guard = self.lock.write().unwrap();
-        let t_ref = self.alloc_impl()?;
+    /// Forbids writing in the notebook, so any number of people can read it without locking.
+    pub fn freeze(self) -> FrozenNotebook<Self> {
+        FrozenNotebook::new(self)
+    }
 
-        *t_ref = t;
-        Some(Handle::new(self, t_ref))
+    /// Forbids writing in the notebook, so any number of people can read it without locking. The
+    /// closure is the last chance to write, and picks the root the frozen notebook is read from.
+    pub fn freeze_with<R: ?Sized, F>(self, f: F) -> FrozenNotebook<Self, R>
+    where
+        F: for<'book> FnOnce(&'book Self) -> &'book R,
+    {
+        FrozenNotebook::with_root(self, f)
     }
 
+    /// Moves every field into a notebook with the other lock without running drop.
     #[inline(always)]
-    fn dealloc<T>(&self, t: &T) -> bool {
-        let _guard = self.lock.write().unwrap();
+    fn relock_impl<M: Default>(mut self) -> MultiNotebook<A, U, M> {
+        self.clear_bumps_impl();
+
+        let notebook = ManuallyDrop::new(self);
 
-        self.dealloc_impl(t)
+        // every field is read exactly once and the original is never dropped
+        unsafe {
+            drop(ptr::read(&notebook.locks));
+
+            MultiNotebook {
+                allocator: ptr::read(&notebook.allocator),
+                size: notebook.size,
+                growth: notebook.growth,
+                max_page_bytes: notebook.max_page_bytes,
+                chapters: ptr::read(&notebook.chapters),
+                bumps: ptr::read(&notebook.bumps),
+                cache: ptr::read(&notebook.cache),
+                locks: Default::default(),
+            }
+        }
     }
-}
 
-unsafe impl<A: BookcaseAllocator, U: Utensil> Sync for PublicMultiNotebook<A, U> {}
+    #[inline(always)]
+    fn child_impl(&self) -> PersonalMultiNotebook<BorrowedAllocator<'_, A>, U> {
+        PersonalMultiNotebook::new(BorrowedAllocator(&self.allocator), self.size, self.growth)
+            .with_max_page_bytes(self.max_page_bytes)
+    }
 
-/// Can only allocate one type. This is especially useful for loading a lot of the same data
-/// into a cache line to increase cache hits during iteration.
-pub struct MonoNotebook<A: BookcaseAllocator, U: Utensil, T, L = ()> {
-    allocator: A,
-    size: SizeStrategy,
-    growth: GrowthStrategy,
-    chapter: RefCell<Chapter<U>>,
-    _pd: PhantomData<T>,
-    lock: L,
-}
+    /// Moves the spare pages of the chapter into the child.
+    #[inline(always)]
+    fn lend_impl(&self, idx: usize, child: &mut PersonalMultiNotebook<BorrowedAllocator<A>, U>) {
+        let spare = self.chapters[idx].borrow_mut().take_spare();
 
-impl<A: BookcaseAllocator, U: Utensil, T, L: Sealed> Sealed for MonoNotebook<A, U, T, L> {}
+        child.chapters[idx].get_mut().set_spare(spare);
+    }
 
-// These implementations are to reduce duplication.
-impl<A: BookcaseAllocator, U: Utensil, T, L> MonoNotebook<A, U, T, L> {
+    /// Erases every page of the chapter of the child and keeps them as spare pages.
     #[inline(always)]
-    fn to_string_impl(&self) -> String {
-        self.chapter.borrow().to_string()
+    fn adopt_impl(&self, idx: usize, child: &mut PersonalMultiNotebook<BorrowedAllocator<A>, U>) {
+        child.chapters[idx].get_mut().give_spare(&mut self.chapters[idx].borrow_mut());
     }
 
-    #[inline(always)]
-    fn alloc_t_impl(&self) -> Option<&mut T> {
-        let t_size = size_of::<T>();
-        let t_align = align_of::<T>();
-        let base_bytes = self.size.base_bytes(t_size, t_align);
-        let mut chapter = self.chapter.borrow_mut();
-        let page_bytes = self.growth.page_bytes(base_bytes, chapter.pages().len());
-        let t = chapter.alloc(&self.allocator, t_size, t_align, page_bytes)?.cast();
+    /// Moves every page of the other notebooks into the first one without copying or allocating.
+    /// Returns None when there are no notebooks.
+    pub fn combine(notebooks: Vec<Self>) -> Option<Self>
+    where
+        A: InterchangeableAllocator,
+    {
+        // every allocator of the type can free the pages of the others
+        unsafe { Self::combine_unchecked(notebooks) }
+    }
 
-        unsafe {
-            Some(&mut *t)
+    /// Moves every page of the other notebooks into the first one without copying or allocating.
+    /// Returns None when there are no notebooks.
+    ///
+    /// # Safety
+    ///
+    /// The pages are given back to the allocator of the first notebook, so it must be able to
+    /// free memory from the allocators of the other notebooks, like clones of the same allocator.
+    pub unsafe fn combine_unchecked(notebooks: Vec<Self>) -> Option<Self> {
+        let mut notebooks = notebooks.into_iter();
+        let mut combined = notebooks.next()?;
+
+        combined.clear_bumps_impl();
+
+        for mut notebook in notebooks {
+            notebook.clear_bumps_impl();
+
+            for (chapter, other) in combined.chapters.iter_mut().zip(notebook.chapters.iter_mut()) {
+                chapter.get_mut().append(other.get_mut());
+            }
         }
+
+        Some(combined)
     }
 
-    #[inline(always)]
-    fn dealloc_t_impl(&self, t: &T) -> bool {
-        self.chapter.borrow_mut().dealloc((t as *const T).cast())
+    pub(crate) fn rollback_impl(&mut self, marker: Marker) {
+        self.clear_bumps_impl();
+
+        for (chapter, offsets) in self.chapters.iter_mut().zip(marker.chapters.iter()) {
+            let chapter = chapter.get_mut();
+
+            chapter.truncate(offsets.len(), &self.allocator);
+            chapter.rewind(offsets);
+        }
     }
 }
 
-impl<A: BookcaseAllocator, U: Utensil, T, L> Drop for MonoNotebook<A, U, T, L> {
+impl<A: BookcaseAllocator, U: Utensil, L> Drop for MultiNotebook<A, U, L> {
     fn drop(&mut self) {
         // Locking is unnecessary since dropping only happens
         // after all references are no longer held.
-        self.chapter.borrow_mut().destroy(&self.allocator);
+        for chapter in self.chapters.iter_mut() {
+            chapter.get_mut().destroy(&self.allocator);
+        }
     }
 }
 
-pub type PersonalMonoNotebook<A, U, T> = MonoNotebook<A, U, T, ()>;
+impl<A: BookcaseAllocator, U: Utensil, L: Locking> MultiNotebook<A, U, L> {
+    /// Allocates through the chunk of the current thread, and only takes a new chunk from the
+    /// newest page when it is full.
+    #[inline(always)]
+    fn cache_impl(&self, layout: Layout) -> Result<NonNull<u8>, Error> {
+        let idx = chapter_idx(layout.align());
 
-impl<A: BookcaseAllocator, U: Utensil, T> PersonalMonoNotebook<A, U, T> {
-    pub fn new(
-        allocator: A,
-        size: SizeStrategy,
-        growth: GrowthStrategy,
-    ) -> PersonalMonoNotebook<A, U, T> {
-        PersonalMonoNotebook {
-            allocator,
-            size,
-            growth,
-            chapter: RefCell::new(Chapter::new()),
-            _pd: PhantomData,
-            lock: (),
+        if let Some(ptr) = self.cache.alloc(idx, layout) {
+            return Ok(ptr);
         }
-    }
-}
 
-impl<A: BookcaseAllocator, U: Utensil, T> ToString for PersonalMonoNotebook<A, U, T> {
-    fn to_string(&self) -> String {
-        self.to_string_impl()
+        let bytes = self.cache.chunk_bytes();
+        let range = match self.bumps[idx].alloc_range(layout, bytes) {
+            Some(range) => range,
+            None => self.locks[idx].locked(|| {
+                let mut chapter = self.chapters[idx].borrow_mut();
+                let page_bytes = self.page_bytes_impl(layout, &chapter)?;
+
+                self.bumps[idx].alloc_range_locked(
+                    &mut chapter,
+                    &self.allocator,
+                    layout,
+                    bytes,
+                    page_bytes,
+                )
+            })??,
+        };
+
+        Ok(self.cache.refill(idx, layout, range))
     }
-}
 
-impl<A: BookcaseAllocator, U: Utensil, T> TypedNotebook<T> for PersonalMonoNotebook<A, U, T> {
-    fn alloc_t(&self) -> Option<&mut T> {
-        self.alloc_t_impl()
-    }
+    /// Runs the closure with a child notebook that writes in the spare pages of this notebook.
+    /// Everything allocated in the child is erased when the closure returns, and its pages become
+    /// spare pages of this notebook, so repeated scopes reuse the same memory. Allocations in this
+    /// notebook are untouched.
+    ///
+    /// Only spare pages are lent, never the unwritten end of the pages this notebook is writing
+    /// in, since it may keep allocating there while the closure runs. So the first scope of a
+    /// notebook without spare pages allocates new pages, even when its own pages have room left.
+    pub fn scope<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&PersonalMultiNotebook<BorrowedAllocator<A>, U>) -> R,
+    {
+        let mut child = self.child_impl();
+
+        // a chapter of the child starts without spare pages if its lock is poisoned
+        for idx in 0..NUM_ALIGNS {
+            let _ = self.locks[idx].locked(|| self.lend_impl(idx, &mut child));
+        }
 
-    fn new_t(&self, t: T) -> Option<Handle<T>> where Self: Sized {
-        let t_ref = self.alloc_t_impl()?;
+        let r = f(&child);
+
+        // the pages of the child are given back to the allocator instead if a lock is poisoned
+        for idx in 0..NUM_ALIGNS {
+            let _ = self.locks[idx].locked(|| self.adopt_impl(idx, &mut child));
+        }
 
-        *t_ref = t;
-        Some(Handle::new(self, t_ref))
+        r
     }
 
-    fn dealloc_t(&self, t: &T) -> bool {
-        self.dealloc_t_impl(t)
+    #[cfg(test)]
+    pub(crate) fn clone_chapters(&self) -> [Vec<Vec<u8>>; NUM_ALIGNS] {
+        let mut chapters: [Vec<Vec<u8>>; NUM_ALIGNS] = Default::default();
+
+        for (idx, chapter) in chapters.iter_mut().enumerate() {
+            *chapter = self.locks[idx].locked(|| self.clone_chapter_impl(idx)).unwrap_or_default();
+        }
+
+        chapters
     }
 }
 
-pub type PublicMonoNotebook<A, U, T> = MonoNotebook<A, U, T, RwLock<()>>;
+impl<A: BookcaseAllocator, U: Utensil, L: Locking> ToString for MultiNotebook<A, U, L> {
+    /// A poisoned chapter can't be read, so the error is written instead.
+    fn to_string(&self) -> String {
+        (0..NUM_ALIGNS)
+            .map(|idx| {
+                self.locks[idx]
+                    .locked(|| self.to_string_impl(idx))
+                    .unwrap_or_else(|error| format!("ch{}:{}", idx + 1, error))
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+impl<A: BookcaseAllocator, U: Utensil, L: Locking> Notebook for MultiNotebook<A, U, L> {
+    /// Public notebooks bump through the newest page without locking, the lock is only taken
+    /// when the page is full. With a thread cache, the chunk of the current thread is bumped
+    /// through instead. The eraser always takes the lock.
+    #[inline(always)]
+    fn try_alloc_layout(&self, layout: Layout) -> Result<NonNull<u8>, Error> {
+        if !L::SHARED || !U::LOCK_FREE {
+            return self.locks[chapter_idx(layout.align())].locked(|| self.alloc_impl(layout))?;
+        }
+
+        if self.cache.fits(layout) {
+            return self.cache_impl(layout);
+        }
+
+        if let Some(ptr) = self.bumps[chapter_idx(layout.align())].alloc(layout) {
+            return Ok(ptr);
+        }
+
+        self.locks[chapter_idx(layout.align())].locked(|| self.bump_impl(layout))?
+    }
+
+    #[inline(always)]
+    unsafe fn dealloc_layout(&self, ptr: NonNull<u8>, layout: Layout) -> bool {
+        self.locks[chapter_idx(layout.align())]
+            .locked(|| self.dealloc_impl(ptr, layout))
+            .unwrap_or(false)
+    }
+}
+
+pub type PersonalMultiNotebook<A, U> = MultiNotebook<A, U, ()>;
 
-impl<A: BookcaseAllocator, U: Utensil, T> PublicMonoNotebook<A, U, T> {
+impl<A: BookcaseAllocator, U: Utensil> PersonalMultiNotebook<A, U> {
     pub fn new(
         allocator: A,
         size: SizeStrategy,
         growth: GrowthStrategy,
-    ) -> PublicMonoNotebook<A, U, T> {
-        PublicMonoNotebook {
+    ) -> PersonalMultiNotebook<A, U> {
+        PersonalMultiNotebook {
             allocator,
             size,
             growth,
-            chapter: RefCell::new(Chapter::new()),
-            _pd: PhantomData,
-            lock: RwLock::new(()),
+            max_page_bytes: MAX_PAGE_BYTES,
+            chapters: [
+                RefCell::new(Chapter::new()),
+                RefCell::new(Chapter::new()),
+                RefCell::new(Chapter::new()),
+                RefCell::new(Chapter::new()),
+                RefCell::new(Chapter::new()),
+            ],
+            bumps: [Bump::new(), Bump::new(), Bump::new(), Bump::new(), Bump::new()],
+            cache: ThreadCache::new(),
+            locks: [(), (), (), (), ()],
+        }
+    }
+
+    /// Shares the notebook between people without copying any pages.
+    pub fn into_public(self) -> PublicMultiNotebook<A, U> {
+        self.relock_impl()
+    }
+
+    /// Shares the notebook between people guarded by locks of the given type without copying
+    /// any pages.
+    pub fn into_public_locked<L: RawLock>(self) -> PublicMultiNotebook<A, U, L> {
+        self.relock_impl()
+    }
+
+    /// Saves the current point in the notebook which can later be rewound to.
+    pub fn marker(&self) -> Marker {
+        self.marker_impl()
+    }
+}
+
+/// Every chapter is guarded by its own lock, which can be any `RawLock`, like `SpinLock`,
+/// `TicketLock` or `Mutex<()>`.
+pub type PublicMultiNotebook<A, U, L = RwLock<()>> = MultiNotebook<A, U, L>;
+
+impl<A: BookcaseAllocator, U: Utensil, L: RawLock> PublicMultiNotebook<A, U, L> {
+    pub fn new(
+        allocator: A,
+        size: SizeStrategy,
+        growth: GrowthStrategy,
+    ) -> PublicMultiNotebook<A, U, L> {
+        PublicMultiNotebook {
+            allocator,
+            size,
+            growth,
+            max_page_bytes: MAX_PAGE_BYTES,
+            chapters: [
+                RefCell::new(Chapter::new()),
+                RefCell::new(Chapter::new()),
+                RefCell::new(Chapter::new()),
+                RefCell::new(Chapter::new()),
+                RefCell::new(Chapter::new()),
+            ],
+            bumps: [Bump::new(), Bump::new(), Bump::new(), Bump::new(), Bump::new()],
+            cache: ThreadCache::new(),
+            locks: Default::default(),
+        }
+    }
+
+    /// Takes back exclusive access to the notebook without copying any pages.
+    pub fn into_personal(self) -> PersonalMultiNotebook<A, U> {
+        self.relock_impl()
+    }
+
+    /// Saves the current point in the notebook which can later be rewound to. Every chapter is
+    /// locked in turn, so allocations in other chapters are not held up. Fails when a lock is
+    /// poisoned.
+    pub fn marker(&self) -> Result<Marker, Error> {
+        self.cache.invalidate();
+
+        let chapters = (0..NUM_ALIGNS)
+            .map(|idx| self.locks[idx].locked(|| self.mark_impl(idx)))
+            .collect::<Result<Vec<Vec<usize>>, Error>>()?;
+
+        Ok(Marker { chapters })
+    }
+}
+
+// Every chapter has its own lock, so several threads may call the allocator at the same time.
+unsafe impl<A, U, L> Sync for MultiNotebook<A, U, L>
+where
+    A: BookcaseAllocator + Sync,
+    U: Utensil + Send,
+    L: RawLock,
+{
+}
+
+/// Can only allocate one type. This is especially useful for loading a lot of the same data
+/// into a cache line to increase cache hits during iteration.
+pub struct MonoNotebook<A: BookcaseAllocator, U: Utensil, T, L = ()> {
+    allocator: A,
+    size: SizeStrategy,
+    growth: GrowthStrategy,
+    max_page_bytes: usize,
+    chapter: RefCell<Chapter<U>>,
+    bump: Bump,
+    cache: ThreadCache,
+    _pd: PhantomData<T>,
+    lock: L,
+}
+
+impl<A: BookcaseAllocator, U: Utensil, T, L: Locking> Sealed for MonoNotebook<A, U, T, L> {}
+
+// These implementations are to reduce duplication.
+impl<A: BookcaseAllocator, U: Utensil, T, L> MonoNotebook<A, U, T, L> {
+    #[inline(always)]
+    fn to_string_impl(&self) -> String {
+        self.chapter.borrow().to_string()
+    }
+
+    #[inline(always)]
+    fn page_bytes_impl(&self, layout: Layout, chapter: &Chapter<U>) -> Result<usize, Error> {
+        if layout.size() > self.max_page_bytes {
+            return Err(Error::OversizedError(layout.size()));
+        }
+
+        let base_bytes = self.size.base_bytes(layout.size(), layout.align());
+
+        Ok(self.growth.page_bytes(base_bytes, chapter.pages().len(), self.max_page_bytes))
+    }
+
+    #[inline(always)]
+    fn alloc_t_impl(&self) -> Result<&mut MaybeUninit<T>, Error> {
+        let layout = Layout::new::<T>();
+        let mut chapter = self.chapter.borrow_mut();
+        let page_bytes = self.page_bytes_impl(layout, &chapter)?;
+        let t = chapter.alloc(&self.allocator, layout, page_bytes)?.cast();
+
+        unsafe {
+            Ok(&mut *t)
+        }
+    }
+
+    /// The locked path of lock-free allocation.
+    #[inline(always)]
+    fn bump_t_impl(&self) -> Result<&mut MaybeUninit<T>, Error> {
+        let layout = Layout::new::<T>();
+        let mut chapter = self.chapter.borrow_mut();
+        let page_bytes = self.page_bytes_impl(layout, &chapter)?;
+        let t = self.bump.alloc_locked(&mut chapter, &self.allocator, layout, page_bytes)?;
+
+        unsafe {
+            Ok(&mut *t.as_ptr().cast())
+        }
+    }
+
+    /// Frees the regions of lock-free allocation, their unused memory is given back to the pages.
+    /// Chunks of every thread are dropped with them.
+    #[inline(always)]
+    fn clear_bump_impl(&mut self) {
+        self.cache.invalidate();
+        self.bump.clear(self.chapter.get_mut());
+    }
+
+    #[inline(always)]
+    fn dealloc_t_impl(&self, t: &T) -> bool {
+        self.chapter.borrow_mut().dealloc((t as *const T).cast(), Layout::new::<T>())
+    }
+
+    #[inline(always)]
+    fn marker_impl(&self) -> Marker {
+        let mut chapter = self.chapter.borrow_mut();
+
+        // memory handed out lock-free after the marker must be erased by it too
+        self.cache.invalidate();
+        self.bump.retire(&mut chapter);
+
+        Marker {
+            chapters: vec![chapter.mark()],
+        }
+    }
+
+    /// Limits how large a page may grow. Values larger than this can't be allocated.
+    pub fn with_max_page_bytes(mut self, max_page_bytes: usize) -> Self {
+        self.max_page_bytes = max_page_bytes.min(MAX_PAGE_BYTES);
+        self
+    }
+
+    /// Gives every thread its own chunk of a page to allocate from, so the notebook is only
+    /// shared when a chunk runs out. Values larger than half a chunk are allocated in the page
+    /// directly. Memory left in a chunk is lost until the notebook is reset or rewound, so chunks
+    /// should be much smaller than a page. Only public notebooks use thread chunks, and not with
+    /// the eraser.
+    pub fn with_thread_cache(mut self, chunk_bytes: usize) -> Self {
+        self.cache = ThreadCache::new().with_chunk_bytes(chunk_bytes);
+        self
+    }
+
+    /// Erases everything while keeping every page, so the notebook can be reused without asking
+    /// the allocator for memory again.
+    pub fn reset(&mut self) {
+        self.clear_bump_impl();
+        self.chapter.get_mut().reset();
+    }
+
+    /// Erases everything while keeping only the largest page. The rest of the pages are given back
+    /// to the allocator.
+    pub fn reset_and_trim(&mut self) {
+        self.clear_bump_impl();
+        self.chapter.get_mut().trim(&self.allocator);
+    }
+
+    /// Erases everything written after the marker was taken.
+    pub fn rewind(&mut self, marker: Marker) {
+        self.clear_bump_impl();
+
+        if let Some(offsets) = marker.chapters.first() {
+            self.chapter.get_mut().rewind(offsets);
+        }
+    }
+
+    /// Saves the current point in the notebook. Everything allocated through the checkpoint can
+    /// be rolled back, which also gives pages created after the checkpoint back to the allocator.
+    pub fn checkpoint(&mut self) -> Checkpoint<'_, Self> {
+        let marker = self.marker_impl();
+
+        Checkpoint::new(self, marker)
+    }
+
+    /// Moves every page of the other notebooks into the first one without copying or allocating.
+    /// Returns None when there are no notebooks.
+    pub fn combine(notebooks: Vec<Self>) -> Option<Self>
+    where
+        A: InterchangeableAllocator,
+    {
+        // every allocator of the type can free the pages of the others
+        unsafe { Self::combine_unchecked(notebooks) }
+    }
+
+    /// Moves every page of the other notebooks into the first one without copying or allocating.
+    /// Returns None when there are no notebooks.
+    ///
+    /// # Safety
+    ///
+    /// The pages are given back to the allocator of the first notebook, so it must be able to
+    /// free memory from the allocators of the other notebooks, like clones of the same allocator.
+    pub unsafe fn combine_unchecked(notebooks: Vec<Self>) -> Option<Self> {
+        let mut notebooks = notebooks.into_iter();
+        let mut combined = notebooks.next()?;
+
+        combined.clear_bump_impl();
+
+        for mut notebook in notebooks {
+            notebook.clear_bump_impl();
+            combined.chapter.get_mut().append(notebook.chapter.get_mut());
+        }
+
+        Some(combined)
+    }
+
+    /// Forbids writing in the notebook, so any number of people can read it without locking.
+    pub fn freeze(self) -> FrozenNotebook<Self> {
+        FrozenNotebook::new(self)
+    }
+
+    /// Forbids writing in the notebook, so any number of people can read it without locking. The
+    /// closure is the last chance to write, and picks the root the frozen notebook is read from.
+    pub fn freeze_with<R: ?Sized, F>(self, f: F) -> FrozenNotebook<Self, R>
+    where
+        F: for<'book> FnOnce(&'book Self) -> &'book R,
+    {
+        FrozenNotebook::with_root(self, f)
+    }
+
+    /// Moves every field into a notebook with the other lock without running drop.
+    #[inline(always)]
+    fn relock_impl<M>(mut self, lock: M) -> MonoNotebook<A, U, T, M> {
+        self.clear_bump_impl();
+
+        let notebook = ManuallyDrop::new(self);
+
+        // every field is read exactly once and the original is never dropped
+        unsafe {
+            drop(ptr::read(&notebook.lock));
+
+            MonoNotebook {
+                allocator: ptr::read(&notebook.allocator),
+                size: notebook.size,
+                growth: notebook.growth,
+                max_page_bytes: notebook.max_page_bytes,
+                chapter: ptr::read(&notebook.chapter),
+                bump: ptr::read(&notebook.bump),
+                cache: ptr::read(&notebook.cache),
+                _pd: PhantomData,
+                lock,
+            }
+        }
+    }
+
+    pub(crate) fn rollback_impl(&mut self, marker: Marker) {
+        self.clear_bump_impl();
+
+        if let Some(offsets) = marker.chapters.first() {
+            let chapter = self.chapter.get_mut();
+
+            chapter.truncate(offsets.len(), &self.allocator);
+            chapter.rewind(offsets);
+        }
+    }
+}
+
+impl<A: BookcaseAllocator, U: Utensil, T, L> Drop for MonoNotebook<A, U, T, L> {
+    fn drop(&mut self) {
+        // Locking is unnecessary since dropping only happens
+        // after all references are no longer held.
+        self.chapter.borrow_mut().destroy(&self.allocator);
+    }
+}
+
+impl<A: BookcaseAllocator, U: Utensil, T, L: Locking> MonoNotebook<A, U, T, L> {
+    /// Allocates through the chunk of the current thread, and only takes a new chunk from the
+    /// newest page when it is full.
+    #[inline(always)]
+    fn cache_t_impl(&self) -> Result<&mut MaybeUninit<T>, Error> {
+        let layout = Layout::new::<T>();
+
+        if let Some(t) = self.cache.alloc(0, layout) {
+            return unsafe { Ok(&mut *t.as_ptr().cast()) };
+        }
+
+        let bytes = self.cache.chunk_bytes();
+        let range = match self.bump.alloc_range(layout, bytes) {
+            Some(range) => range,
+            None => self.lock.locked(|| {
+                let mut chapter = self.chapter.borrow_mut();
+                let page_bytes = self.page_bytes_impl(layout, &chapter)?;
+
+                self.bump.alloc_range_locked(
+                    &mut chapter,
+                    &self.allocator,
+                    layout,
+                    bytes,
+                    page_bytes,
+                )
+            })??,
+        };
+        let t = self.cache.refill(0, layout, range);
+
+        unsafe {
+            Ok(&mut *t.as_ptr().cast())
         }
     }
 }
 
-impl<A: BookcaseAllocator, U: Utensil, T> ToString for PublicMonoNotebook<A, U, T> {
+impl<A: BookcaseAllocator, U: Utensil, T, L: Locking> ToString for MonoNotebook<A, U, T, L> {
+    /// A poisoned notebook can't be read, so the error is written instead.
     fn to_string(&self) -> String {
-        let _guard = self.lock.read().unwrap();
+        self.lock
+            .locked(|| self.to_string_impl())
+            .unwrap_or_else(|error| error.to_string())
+    }
+}
+
+impl<A: BookcaseAllocator, U: Utensil, T, L: Locking> TypedNotebook<T> for MonoNotebook<A, U, T, L> {
+    /// Public notebooks bump through the newest page without locking, the lock is only taken
+    /// when the page is full. With a thread cache, the chunk of the current thread is bumped
+    /// through instead. The eraser always takes the lock.
+    fn try_alloc_uninit_t(&self) -> Result<&mut MaybeUninit<T>, Error> {
+        if !L::SHARED || !U::LOCK_FREE {
+            return self.lock.locked(|| self.alloc_t_impl())?;
+        }
 
-        self.to_string_impl()
+        if self.cache.fits(Layout::new::<T>()) {
+            return self.cache_t_impl();
+        }
+
+        if let Some(t) = self.bump.alloc(Layout::new::<T>()) {
+            return unsafe { Ok(&mut *t.as_ptr().cast()) };
+        }
+
+        self.lock.locked(|| self.bump_t_impl())?
+    }
+
+    unsafe fn dealloc_t(&self, t: &T) -> bool {
+        self.lock.locked(|| self.dealloc_t_impl(t)).unwrap_or(false)
     }
 }
 
-impl<A: BookcaseAllocator, U: Utensil, T> TypedNotebook<T> for PublicMonoNotebook<A, U, T> {
-    fn alloc_t(&self) -> Option<&mut T> {
-        let _guard = self.lock.write().unwrap();
+pub type PersonalMonoNotebook<A, U, T> = MonoNotebook<A, U, T, ()>;
+
+impl<A: BookcaseAllocator, U: Utensil, T> PersonalMonoNotebook<A, U, T> {
+    pub fn new(
+        allocator: A,
+        size: SizeStrategy,
+        growth: GrowthStrategy,
+    ) -> PersonalMonoNotebook<A, U, T> {
+        PersonalMonoNotebook {
+            allocator,
+            size,
+            growth,
+            max_page_bytes: MAX_PAGE_BYTES,
+            chapter: RefCell::new(Chapter::new()),
+            bump: Bump::new(),
+            cache: ThreadCache::new(),
+            _pd: PhantomData,
+            lock: (),
+        }
+    }
 
-        self.alloc_t_impl()
+    /// Shares the notebook between people without copying any pages.
+    pub fn into_public(self) -> PublicMonoNotebook<A, U, T> {
+        self.relock_impl(RwLock::new(()))
     }
 
-    fn new_t(&self, t: T) -> Option<Handle<T>> where Self: Sized {
-        let _guard = self.lock.write().unwrap();
-        let t_ref = self.alloc_t_impl()?;
+    /// Shares the notebook between people guarded by a lock of the given type without copying
+    /// any pages.
+    pub fn into_public_locked<L: RawLock>(self) -> PublicMonoNotebook<A, U, T, L> {
+        self.relock_impl(L::default())
+    }
+
+    /// Saves the current point in the notebook which can later be rewound to.
+    pub fn marker(&self) -> Marker {
+        self.marker_impl()
+    }
+}
 
-        *t_ref = t;
-        Some(Handle::new(self, t_ref))
+/// The lock can be any `RawLock`, like `SpinLock`, `TicketLock` or `Mutex<()>`.
+pub type PublicMonoNotebook<A, U, T, L = RwLock<()>> = MonoNotebook<A, U, T, L>;
+
+impl<A: BookcaseAllocator, U: Utensil, T, L: RawLock> PublicMonoNotebook<A, U, T, L> {
+    pub fn new(
+        allocator: A,
+        size: SizeStrategy,
+        growth: GrowthStrategy,
+    ) -> PublicMonoNotebook<A, U, T, L> {
+        PublicMonoNotebook {
+            allocator,
+            size,
+            growth,
+            max_page_bytes: MAX_PAGE_BYTES,
+            chapter: RefCell::new(Chapter::new()),
+            bump: Bump::new(),
+            cache: ThreadCache::new(),
+            _pd: PhantomData,
+            lock: L::default(),
+        }
     }
 
-    fn dealloc_t(&self, t: &T) -> bool {
-        let _guard = self.lock.write().unwrap();
+    /// Takes back exclusive access to the notebook without copying any pages.
+    pub fn into_personal(self) -> PersonalMonoNotebook<A, U, T> {
+        self.relock_impl(())
+    }
 
-        self.dealloc_t_impl(t)
+    /// Saves the current point in the notebook which can later be rewound to. Fails when the lock
+    /// is poisoned.
+    pub fn marker(&self) -> Result<Marker, Error> {
+        self.lock.locked(|| self.marker_impl())
     }
 }
 
-unsafe impl<A: BookcaseAllocator, U: Utensil, T> Sync for PublicMonoNotebook<A, U, T> {}
+unsafe impl<A, U, T, L> Sync for MonoNotebook<A, U, T, L>
+where
+    A: BookcaseAllocator + Sync,
+    U: Utensil + Send,
+    L: RawLock,
+{
+}
+
+/// Moves the value into the allocated memory and wraps it in a handle, or hands the value back.
+#[inline(always)]
+fn handle<'book, T>(
+    notebook: &'book dyn TypedNotebook<T>,
+    t_ref: Result<&'book mut MaybeUninit<T>, Error>,
+    t: T,
+) -> Result<Handle<'book, T>, NewError<T>> {
+    match t_ref {
+        Ok(t_ref) => Ok(Handle::new(notebook, t_ref.write(t))),
+        Err(error) => Err(NewError { error, value: t }),
+    }
+}
 
+/// Alignments of 16 and above share the last chapter, the utensil pads each allocation to its
+/// alignment.
 #[inline(always)]
 fn chapter_idx(t_align: usize) -> usize {
     t_align.trailing_zeros().min(NUM_ALIGNS as u32 - 1) as usize
@@ -452,6 +1261,9 @@ fn chapter_idx(t_align: usize) -> usize {
 
 #[cfg(test)]
 mod tests {
+    use std::mem::{align_of, size_of};
+
+    use super::chapter_idx;
     use crate::*;
 
     fn assert_send<T: Send>() {}
@@ -472,6 +1284,14 @@ mod tests {
         assert_sync::<PublicMonoNotebook<StdAllocator, Pen, usize>>();
     }
 
+    #[test]
+    fn ensure_public_notebooks_need_sync_allocators() {
+        type Inner = PersonalMultiNotebook<StdAllocator, Pen>;
+
+        assert_not_sync!(PublicMultiNotebook<NotebookAllocator<'static, Inner>, Pen>);
+        assert_not_sync!(PublicMonoNotebook<NotebookAllocator<'static, Inner>, Pen, usize>);
+    }
+
 
     #[derive(Copy, Clone, Debug, Eq, PartialEq)]
     struct TestStruct {
@@ -540,4 +1360,678 @@ mod tests {
 
         assert_eq!(TestStruct { a: 16909060, b: -67305985 }, *s1.expect(line_str!()));
     }
+
+    #[test]
+    fn test_value_larger_than_page_gets_own_page() {
+        let notebook = PersonalMultiNotebook::<_, Pen>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(1),
+            GrowthStrategy::Constant,
+        );
+
+        notebook.alloc_init(1usize).expect(line_str!());
+        notebook.alloc_init([2usize; 3]).expect(line_str!());
+        notebook.alloc_init(3usize).expect(line_str!());
+
+        let pages = &notebook.clone_chapters()[3];
+
+        assert_eq!(3, pages.len());
+        assert_eq!(size_of::<usize>(), pages[0].len());
+        assert_eq!(3 * size_of::<usize>(), pages[1].len());
+        assert_eq!(size_of::<usize>(), pages[2].len());
+        assert_eq!(2, pages[1][2 * size_of::<usize>()]);
+    }
+
+    #[derive(Copy, Clone)]
+    #[repr(align(64))]
+    struct CacheLine([u8; 64]);
+
+    #[derive(Copy, Clone)]
+    #[repr(align(4096))]
+    struct MemoryPage([u8; 4096]);
+
+    #[test]
+    fn test_over_aligned_types_share_chapter() {
+        let notebook = PersonalMultiNotebook::<_, Pencil>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(1024),
+            GrowthStrategy::Constant,
+        );
+
+        notebook.alloc_init(1u128).expect(line_str!());
+        let line = notebook.alloc_init(CacheLine([2; 64])).expect(line_str!());
+        notebook.alloc_init(3u128).expect(line_str!());
+        let page = notebook.alloc_init(MemoryPage([4; 4096])).expect(line_str!());
+        let line2 = notebook.alloc_init(CacheLine([5; 64])).expect(line_str!());
+
+        assert_eq!(0, line as *mut CacheLine as usize % 64);
+        assert_eq!(0, page as *mut MemoryPage as usize % 4096);
+        assert_eq!(0, line2 as *mut CacheLine as usize % 64);
+        assert_eq!([2; 64], line.0);
+        assert_eq!([4; 4096], page.0);
+    }
+
+    #[test]
+    fn test_over_aligned_mono_notebook() {
+        let typed_notebook = PersonalMonoNotebook::<_, Pen, CacheLine>::new(
+            StdAllocator,
+            SizeStrategy::ItemsPerPage(3),
+            GrowthStrategy::Linear(2),
+        );
+
+        for idx in 0..8 {
+            let line = typed_notebook.alloc_init_t(CacheLine([idx; 64])).expect(line_str!());
+
+            assert_eq!(0, line as *mut CacheLine as usize % 64);
+        }
+    }
+
+    #[test]
+    fn test_mono_notebook_of_zero_sized_type() {
+        let typed_notebook = PersonalMonoNotebook::<_, Pen, ()>::new(
+            StdAllocator,
+            SizeStrategy::ItemsPerPage(4),
+            GrowthStrategy::Exponential,
+        );
+
+        typed_notebook.alloc_init_t(()).expect(line_str!());
+        typed_notebook.new_t(()).expect(line_str!());
+    }
+
+    #[test]
+    fn test_exponential_growth_stops_at_max_page_bytes() {
+        let notebook = PersonalMultiNotebook::<_, Pen>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(1),
+            GrowthStrategy::Exponential,
+        ).with_max_page_bytes(32);
+
+        for idx in 0..64u64 {
+            notebook.alloc_init(idx).expect(line_str!());
+        }
+
+        let pages = notebook.clone_chapter_impl(3);
+
+        assert!(pages.iter().all(|p| p.len() <= 32));
+        assert_eq!(32, pages.last().expect(line_str!()).len());
+    }
+
+    #[test]
+    fn test_value_larger_than_max_page_bytes_is_an_error() {
+        let notebook = PersonalMultiNotebook::<_, Pen>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(1),
+            GrowthStrategy::Exponential,
+        ).with_max_page_bytes(32);
+
+        assert!(matches!(notebook.try_alloc::<[u8; 64]>(), Err(Error::OversizedError(64))));
+        assert!(notebook.alloc_init([0u8; 32]).is_some());
+    }
+
+    #[test]
+    fn test_alloc_uninit_and_alloc_with() {
+        let notebook = PersonalMultiNotebook::<_, Pencil>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(4),
+            GrowthStrategy::Constant,
+        );
+
+        let uninit = notebook.alloc_uninit::<String>().expect(line_str!());
+        let s = uninit.write(String::from("uninit"));
+
+        assert_eq!("uninit", s);
+
+        // the closure may allocate in the same notebook
+        let t = notebook
+            .alloc_with(|| {
+                let a = notebook.alloc_init(3usize).expect(line_str!());
+
+                TestStruct { a: *a, b: -3 }
+            })
+            .expect(line_str!());
+
+        assert_eq!(TestStruct { a: 3, b: -3 }, *t);
+
+        unsafe {
+            std::ptr::drop_in_place(s);
+        }
+    }
+
+    #[test]
+    fn test_alloc_with_values_that_need_drop() {
+        let notebook = PersonalMultiNotebook::<_, Pen>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(8),
+            GrowthStrategy::Constant,
+        );
+        let typed_notebook = PersonalMonoNotebook::<_, Pen, Vec<u8>>::new(
+            StdAllocator,
+            SizeStrategy::ItemsPerPage(2),
+            GrowthStrategy::Constant,
+        );
+
+        let s = notebook.alloc_with(|| String::from("with")).expect(line_str!());
+        let v = typed_notebook.alloc_with_t(|| vec![1, 2, 3]).expect(line_str!());
+
+        assert_eq!("with", s);
+        assert_eq!(vec![1, 2, 3], *v);
+
+        // neither notebook drops the values
+        unsafe {
+            std::ptr::drop_in_place(s);
+            std::ptr::drop_in_place(v);
+        }
+    }
+
+    #[test]
+    fn test_mono_notebook_alloc_uninit_and_alloc_with() {
+        let typed_notebook = PublicMonoNotebook::<_, Pencil, TestStruct>::new(
+            StdAllocator,
+            SizeStrategy::ItemsPerPage(2),
+            GrowthStrategy::Constant,
+        );
+
+        let t1 = typed_notebook.alloc_uninit_t().expect(line_str!()).write(TestStruct {
+            a: 1,
+            b: -1,
+        });
+        let t2 = typed_notebook.alloc_with_t(|| TestStruct { a: 2, b: -2 }).expect(line_str!());
+        let t3 = typed_notebook.new_t(TestStruct { a: 3, b: -3 }).expect(line_str!());
+
+        assert_eq!(TestStruct { a: 1, b: -1 }, *t1);
+        assert_eq!(TestStruct { a: 2, b: -2 }, *t2);
+        assert_eq!(TestStruct { a: 3, b: -3 }, *t3);
+    }
+
+    #[test]
+    fn test_alloc_slices_and_strs() {
+        let notebook = PersonalMultiNotebook::<_, Pen>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(4),
+            GrowthStrategy::Constant,
+        );
+
+        let copied = notebook.alloc_slice_copy(&[1u16, 2, 3]).expect(line_str!());
+        let filled = notebook.alloc_slice_fill_with(5, |idx| idx as u64 * 10).expect(line_str!());
+        let collected = notebook.alloc_slice_from_iter((0..4u32).map(|idx| idx * idx));
+        let s = notebook.alloc_str("Hello, World!").expect(line_str!());
+
+        copied[0] = 7;
+        s.make_ascii_uppercase();
+
+        assert_eq!(&[7, 2, 3], copied);
+        assert_eq!(&[0, 10, 20, 30, 40], filled);
+        assert_eq!(Some(&mut [0, 1, 4, 9][..]), collected);
+        assert_eq!("HELLO, WORLD!", s);
+        assert_eq!(0, filled.as_ptr() as usize % align_of::<u64>());
+    }
+
+    #[test]
+    fn test_alloc_slices_of_values_that_need_drop() {
+        let notebook = PersonalMultiNotebook::<_, Pen>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(8),
+            GrowthStrategy::Constant,
+        );
+
+        let tokens = notebook.alloc_slice_fill_with(3, |idx| idx.to_string()).expect(line_str!());
+        let table = notebook
+            .alloc_slice_from_iter(vec![String::from("a"), String::from("b")])
+            .expect(line_str!());
+
+        assert_eq!(["0", "1", "2"], tokens);
+        assert_eq!(["a", "b"], table);
+
+        // the notebook never drops the elements
+        unsafe {
+            std::ptr::drop_in_place(tokens);
+            std::ptr::drop_in_place(table);
+        }
+    }
+
+    #[test]
+    fn test_alloc_slice_uninit_respects_alignment() {
+        let notebook = PersonalMultiNotebook::<_, Pen>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(4),
+            GrowthStrategy::Constant,
+        );
+
+        notebook.alloc_str("x").expect(line_str!());
+        let lines = notebook.alloc_slice_uninit::<CacheLine>(3).expect(line_str!());
+        let empty = notebook.alloc_slice_copy::<u64>(&[]).expect(line_str!());
+
+        assert_eq!(3, lines.len());
+        assert_eq!(0, lines.as_ptr() as usize % 64);
+        assert!(empty.is_empty());
+        assert!(matches!(
+            notebook.try_alloc_slice_uninit::<u64>(usize::MAX),
+            Err(Error::LayoutError),
+        ));
+    }
+
+    #[test]
+    fn test_reset_keeps_pages() {
+        let mut notebook = PersonalMultiNotebook::<_, Pen>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(2),
+            GrowthStrategy::Constant,
+        );
+
+        for idx in 0..8u64 {
+            notebook.alloc_init(idx).expect(line_str!());
+        }
+
+        notebook.reset();
+
+        // refilling the notebook fits in the pages it already has
+        for idx in 0..8u64 {
+            notebook.alloc_init(idx).expect(line_str!());
+        }
+
+        assert_eq!(4, notebook.clone_chapters()[3].len());
+    }
+
+    #[test]
+    fn test_reset_and_trim_keeps_largest_page() {
+        let mut notebook = PersonalMultiNotebook::<_, Pencil>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(1),
+            GrowthStrategy::Exponential,
+        );
+
+        for idx in 0..15u64 {
+            notebook.alloc_init(idx).expect(line_str!());
+        }
+
+        assert_eq!(vec![8, 16, 32, 64], page_sizes(&notebook.clone_chapters()[3]));
+
+        notebook.reset_and_trim();
+
+        assert_eq!(vec![64], page_sizes(&notebook.clone_chapters()[3]));
+
+        for idx in 0..8u64 {
+            notebook.alloc_init(idx).expect(line_str!());
+        }
+
+        assert_eq!(vec![64], page_sizes(&notebook.clone_chapters()[3]));
+    }
+
+    #[test]
+    fn test_reset_mono_notebook() {
+        let mut typed_notebook = PublicMonoNotebook::<_, Eraser, u32>::new(
+            StdAllocator,
+            SizeStrategy::ItemsPerPage(4),
+            GrowthStrategy::Constant,
+        );
+
+        let first = typed_notebook.alloc_init_t(1).expect(line_str!()) as *mut u32 as usize;
+
+        typed_notebook.alloc_init_t(2).expect(line_str!());
+        typed_notebook.reset();
+
+        let reused = typed_notebook.alloc_init_t(3).expect(line_str!()) as *mut u32 as usize;
+
+        assert_eq!(first, reused);
+    }
+
+    fn page_sizes(pages: &[Vec<u8>]) -> Vec<usize> {
+        pages.iter().map(Vec::len).collect()
+    }
+
+    #[test]
+    fn test_scope_reuses_pages_and_keeps_parent() {
+        let notebook = PersonalMultiNotebook::<_, Pen>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(4),
+            GrowthStrategy::Constant,
+        );
+
+        let kept = notebook.alloc_init(42u64).expect(line_str!());
+
+        let first = notebook.scope(|child| {
+            for idx in 0..8u64 {
+                child.alloc_init(idx).expect(line_str!());
+            }
+
+            child.alloc_init(8u64).expect(line_str!()) as *mut u64 as usize
+        });
+
+        let second = notebook.scope(|child| {
+            for idx in 0..8u64 {
+                child.alloc_init(idx).expect(line_str!());
+            }
+
+            child.alloc_init(8u64).expect(line_str!()) as *mut u64 as usize
+        });
+
+        assert_eq!(first, second);
+        assert_eq!(42, *kept);
+        assert_eq!(1, notebook.clone_chapters()[3].len());
+    }
+
+    #[test]
+    fn test_nested_scopes_of_public_notebook() {
+        let notebook = PublicMultiNotebook::<_, Pencil>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(4),
+            GrowthStrategy::Constant,
+        );
+
+        let total = notebook.scope(|child| {
+            let a = child.alloc_init(1u32).expect(line_str!());
+            let b = child.scope(|grandchild| {
+                *grandchild.alloc_init(2u32).expect(line_str!())
+            });
+
+            *a + b
+        });
+
+        assert_eq!(3, total);
+
+        // the spare pages of the child are written in by the parent
+        notebook.alloc_init(3u32).expect(line_str!());
+
+        assert_eq!(1, notebook.clone_chapters()[2].len());
+    }
+
+    #[test]
+    fn test_combine_moves_pages_from_threads() {
+        let workers: Vec<_> = (0..4u64)
+            .map(|t| {
+                std::thread::spawn(move || {
+                    let notebook = PersonalMultiNotebook::<_, Pen>::new(
+                        StdAllocator,
+                        SizeStrategy::WordsPerPage(2),
+                        GrowthStrategy::Constant,
+                    );
+
+                    let addrs: Vec<usize> = (0..3)
+                        .map(|idx| notebook.alloc_init(t * 10 + idx).expect(line_str!()))
+                        .map(|value| value as *mut u64 as usize)
+                        .collect();
+
+                    (notebook, addrs)
+                })
+            })
+            .collect();
+
+        let (notebooks, addrs): (Vec<_>, Vec<_>) = workers
+            .into_iter()
+            .map(|worker| worker.join().expect(line_str!()))
+            .unzip();
+
+        let notebook = PersonalMultiNotebook::combine(notebooks).expect(line_str!());
+
+        assert_eq!(8, notebook.clone_chapters()[3].len());
+
+        for (t, addrs) in addrs.iter().enumerate() {
+            for (idx, addr) in addrs.iter().enumerate() {
+                assert_eq!((t * 10 + idx) as u64, unsafe { *(*addr as *const u64) });
+            }
+        }
+    }
+
+    #[test]
+    fn test_combine_mono_notebooks() {
+        let notebooks: Vec<_> = (0..3u32)
+            .map(|t| {
+                let typed_notebook = PersonalMonoNotebook::<_, Pencil, u32>::new(
+                    StdAllocator,
+                    SizeStrategy::ItemsPerPage(4),
+                    GrowthStrategy::Constant,
+                );
+
+                typed_notebook.alloc_init_t(t).expect(line_str!());
+                typed_notebook
+            })
+            .collect();
+
+        let typed_notebook = PersonalMonoNotebook::combine(notebooks).expect(line_str!());
+
+        // the pages of every notebook have room left to write in
+        for idx in 0..9 {
+            typed_notebook.alloc_init_t(idx).expect(line_str!());
+        }
+
+        assert_eq!(3, typed_notebook.chapter.borrow().pages().len());
+        assert!(PersonalMonoNotebook::<StdAllocator, Pen, u32>::combine(vec![]).is_none());
+    }
+
+    #[test]
+    fn test_combine_unchecked_with_same_inner_notebook() {
+        let inner = PersonalMultiNotebook::<_, Pen>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(64),
+            GrowthStrategy::Constant,
+        );
+
+        let notebooks: Vec<_> = (0..2u64)
+            .map(|t| {
+                let notebook = PersonalMultiNotebook::<_, Pen>::new(
+                    NotebookAllocator(&inner),
+                    SizeStrategy::WordsPerPage(2),
+                    GrowthStrategy::Constant,
+                );
+
+                notebook.alloc_init(t).expect(line_str!());
+                notebook
+            })
+            .collect();
+
+        // the pages of both notebooks are in the same inner notebook
+        let notebook = unsafe { PersonalMultiNotebook::combine_unchecked(notebooks) };
+
+        assert_eq!(2, notebook.expect(line_str!()).clone_chapters()[3].len());
+    }
+
+    #[test]
+    fn test_personal_into_public_and_back() {
+        let notebook = PersonalMultiNotebook::<_, Pencil>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(4),
+            GrowthStrategy::Constant,
+        );
+
+        let addr = notebook.alloc_init(7u64).expect(line_str!()) as *mut u64 as usize;
+        let notebook = std::sync::Arc::new(notebook.into_public());
+
+        let workers: Vec<_> = (0..4u64)
+            .map(|t| {
+                let notebook = notebook.clone();
+
+                std::thread::spawn(move || *notebook.alloc_init(t).expect(line_str!()))
+            })
+            .collect();
+
+        for (t, worker) in workers.into_iter().enumerate() {
+            assert_eq!(t as u64, worker.join().expect(line_str!()));
+        }
+
+        let notebook = std::sync::Arc::try_unwrap(notebook)
+            .map_err(|_| ())
+            .expect(line_str!())
+            .into_personal();
+
+        assert_eq!(7, unsafe { *(addr as *const u64) });
+        assert_eq!(2, notebook.clone_chapters()[3].len());
+    }
+
+    #[test]
+    fn test_mono_into_public_and_back() {
+        let typed_notebook = PersonalMonoNotebook::<_, Pen, u32>::new(
+            StdAllocator,
+            SizeStrategy::ItemsPerPage(4),
+            GrowthStrategy::Constant,
+        );
+
+        let addr = typed_notebook.alloc_init_t(1).expect(line_str!()) as *mut u32 as usize;
+        let typed_notebook = typed_notebook.into_public();
+
+        typed_notebook.alloc_init_t(2).expect(line_str!());
+
+        let typed_notebook = typed_notebook.into_personal();
+        let next = typed_notebook.alloc_init_t(3).expect(line_str!()) as *mut u32 as usize;
+
+        assert_eq!(1, unsafe { *(addr as *const u32) });
+        assert_eq!(addr + 2 * size_of::<u32>(), next);
+    }
+
+    #[test]
+    fn test_failed_new_hands_value_back() {
+        let notebook = PersonalMultiNotebook::<_, Pen>::new(
+            crate::test::FailingAllocator,
+            SizeStrategy::WordsPerPage(4),
+            GrowthStrategy::Constant,
+        );
+
+        let error = notebook.try_new(String::from("kept")).expect_err(line_str!());
+
+        assert!(matches!(error.error, Error::AllocError { .. }));
+        assert_eq!("kept", error.into_value());
+        assert!(matches!(notebook.try_alloc::<u8>(), Err(Error::AllocError { .. })));
+        assert!(notebook.alloc::<u8>().is_none());
+    }
+
+    #[test]
+    fn test_poisoned_lock_is_an_error() {
+        let notebook = PublicMonoNotebook::<_, Pen, u64>::new(
+            crate::test::PanickingAllocator,
+            SizeStrategy::ItemsPerPage(4),
+            GrowthStrategy::Constant,
+        );
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            notebook.alloc_t()
+        }));
+
+        assert!(result.is_err());
+        assert!(matches!(notebook.try_alloc_t(), Err(Error::PoisonError)));
+        assert_eq!(7, notebook.try_new_t(7).expect_err(line_str!()).value);
+    }
+
+    #[test]
+    fn test_poisoned_chapter_leaves_other_chapters_usable() {
+        let notebook = PublicMultiNotebook::<_, Pen>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(4),
+            GrowthStrategy::Constant,
+        );
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            notebook.locks[chapter_idx(1)].locked(|| panic!())
+        }));
+
+        assert!(result.is_err());
+        assert!(matches!(notebook.try_alloc::<u8>(), Err(Error::PoisonError)));
+        assert!(matches!(notebook.marker(), Err(Error::PoisonError)));
+        assert_eq!(7, *notebook.alloc_init(7u64).expect(line_str!()));
+    }
+
+    #[test]
+    fn test_chapters_are_locked_independently() {
+        let notebook = std::sync::Arc::new(PublicMultiNotebook::<_, Pen, SpinLock>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(1),
+            GrowthStrategy::Constant,
+        ));
+
+        // every u64 needs a new page, so this would never finish if the u8 chapter shared its lock
+        let sum = notebook.locks[chapter_idx(1)].locked(|| {
+            let worker = notebook.clone();
+
+            std::thread::spawn(move || {
+                (0..100u64).map(|idx| *worker.alloc_init(idx).expect(line_str!())).sum::<u64>()
+            })
+            .join()
+            .expect(line_str!())
+        });
+
+        assert_eq!(4950, sum.expect(line_str!()));
+        assert_eq!(100, notebook.clone_chapters()[3].len());
+    }
+
+    #[test]
+    fn test_pencil_reuses_dropped_handles() {
+        let notebook = PersonalMultiNotebook::<_, Pencil>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(8),
+            GrowthStrategy::Constant,
+        );
+
+        let first = notebook.new(String::from("first")).expect(line_str!());
+        let first_addr = &*first as *const String as usize;
+        drop(first);
+
+        let second = notebook.new(String::from("second")).expect(line_str!());
+
+        assert_eq!(first_addr, &*second as *const String as usize);
+        assert_eq!("second", *second);
+        assert_eq!(1, notebook.clone_chapters()[3].len());
+    }
+
+    #[test]
+    fn test_eraser_rewinds_to_marker() {
+        let mut notebook = PersonalMultiNotebook::<_, Eraser>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(4),
+            GrowthStrategy::Constant,
+        );
+
+        notebook.alloc_init(1u64).expect(line_str!());
+        let marker = notebook.marker();
+        let scratch = notebook.alloc_init(2u64).expect(line_str!()) as *mut u64 as usize;
+        notebook.alloc_init(3u64).expect(line_str!());
+        notebook.alloc_init(4u8).expect(line_str!());
+
+        notebook.rewind(marker);
+
+        assert_eq!(scratch, notebook.alloc_init(5u64).expect(line_str!()) as *mut u64 as usize);
+        notebook.alloc_init(6u64).expect(line_str!());
+        notebook.alloc_init(7u64).expect(line_str!());
+        notebook.alloc_init(8u8).expect(line_str!());
+
+        let chapters = notebook.clone_chapters();
+
+        assert_eq!(1, chapters[3].len());
+        assert_eq!(8u8, chapters[0][0][0]);
+    }
+
+    #[test]
+    fn test_eraser_pops_dropped_handle() {
+        let notebook = PersonalMonoNotebook::<_, Eraser, TestStruct>::new(
+            StdAllocator,
+            SizeStrategy::ItemsPerPage(4),
+            GrowthStrategy::Constant,
+        );
+
+        let scratch = notebook.new_t(TestStruct { a: 1, b: -1 }).expect(line_str!());
+        let scratch_addr = &*scratch as *const TestStruct as usize;
+        drop(scratch);
+
+        let s2 = notebook.alloc_init_t(TestStruct { a: 2, b: -2 }).expect(line_str!());
+
+        assert_eq!(scratch_addr, s2 as *mut TestStruct as usize);
+    }
+
+    #[test]
+    fn test_pencil_mono_notebook_reuses_dealloc() {
+        let typed_notebook = PersonalMonoNotebook::<_, Pencil, TestStruct>::new(
+            StdAllocator,
+            SizeStrategy::ItemsPerPage(2),
+            GrowthStrategy::Constant,
+        );
+
+        let s1 = typed_notebook.alloc_init_t(TestStruct { a: 1, b: -1 }).expect(line_str!());
+        let s1_addr = s1 as *mut TestStruct as usize;
+        typed_notebook.alloc_init_t(TestStruct { a: 2, b: -2 }).expect(line_str!());
+
+        unsafe {
+            assert!(typed_notebook.dealloc_t(s1));
+        }
+
+        let s3 = typed_notebook.alloc_init_t(TestStruct { a: 3, b: -3 }).expect(line_str!());
+
+        assert_eq!(s1_addr, s3 as *mut TestStruct as usize);
+    }
 }
@@ -3,6 +3,7 @@ use core::marker::PhantomData;
 use core::ptr::NonNull;
 
 use crate::allocator::BookcaseAllocator;
+use crate::error::Error;
 use crate::seal::Sealed;
 
 pub(crate) struct Page<U, T=u8> {
@@ -13,15 +14,18 @@ pub(crate) struct Page<U, T=u8> {
 }
 
 impl<U: Utensil> Page<U> {
-    pub(crate) fn create(layout: Layout, allocator: &dyn BookcaseAllocator) -> Option<Page<U>> {
+    pub(crate) fn create(
+        layout: Layout,
+        allocator: &dyn BookcaseAllocator,
+    ) -> Result<Page<U>, Error> {
         if usize::BITS < 64 && layout.size() > isize::MAX as usize {
-            return None;
+            return Err(Error::OversizedError(layout.size()));
         }
 
-        let ptr = allocator.allocate(layout).ok()?.cast().as_ptr();
+        let ptr = allocator.allocate(layout)?.cast().as_ptr();
         let utensil = U::new(ptr as usize, layout);
 
-        Some(Page {
+        Ok(Page {
             ptr: unsafe { NonNull::new_unchecked(ptr) },
             layout,
             utensil,
@@ -41,13 +45,23 @@ impl<U: Utensil> Page<U> {
     }
 
     #[inline(always)]
-    pub(crate) fn can_alloc(&self, bytes: usize) -> bool {
-        self.utensil.can_alloc(bytes)
+    pub(crate) fn size(&self) -> usize {
+        self.layout.size()
     }
 
     #[inline(always)]
-    pub(crate) fn alloc(&mut self, bytes: usize) -> *mut u8 {
-        self.utensil.alloc(bytes)
+    pub(crate) fn addr(&self) -> usize {
+        self.ptr.as_ptr() as usize
+    }
+
+    #[inline(always)]
+    pub(crate) fn can_alloc(&self, layout: Layout) -> bool {
+        self.utensil.can_alloc(layout)
+    }
+
+    #[inline(always)]
+    pub(crate) fn alloc(&mut self, layout: Layout) -> *mut u8 {
+        self.utensil.alloc(layout)
     }
 
     #[inline(always)]
@@ -56,8 +70,18 @@ impl<U: Utensil> Page<U> {
     }
 
     #[inline(always)]
-    pub(crate) fn dealloc(&mut self, ptr: *const u8) {
-        self.utensil.dealloc(ptr);
+    pub(crate) fn dealloc(&mut self, ptr: *const u8, layout: Layout) {
+        self.utensil.dealloc(ptr, layout);
+    }
+
+    #[inline(always)]
+    pub(crate) fn offset(&self) -> usize {
+        self.utensil.offset()
+    }
+
+    #[inline(always)]
+    pub(crate) fn rewind(&mut self, offset: usize) {
+        self.utensil.rewind(offset);
     }
 
     pub(crate) fn destroy(&mut self, allocator: &dyn BookcaseAllocator) {
@@ -87,12 +111,36 @@ impl<U> ToString for Page<U> {
     }
 }
 
+/// The number of bytes needed to move the address up to the alignment.
+#[inline(always)]
+pub(crate) fn padding(addr: usize, align: usize) -> usize {
+    addr.wrapping_neg() & (align - 1)
+}
+
+/// Decides where in a page each allocation is placed. Every allocation is described by its
+/// layout, so a utensil is free to pad, round up, or group allocations however it likes as long as
+/// the returned address is aligned to the layout and the layout's size fits before the end of the
+/// page.
 pub trait Utensil: Send + Sync + Sealed {
+    /// The page starts at addr and spans the whole layout.
     fn new(addr: usize, layout: Layout) -> Self;
-    fn can_alloc(&self, bytes: usize) -> bool;
-    fn alloc(&mut self, bytes: usize) -> *mut u8;
+    fn can_alloc(&self, layout: Layout) -> bool;
+    fn alloc(&mut self, layout: Layout) -> *mut u8;
     fn can_dealloc(&self, ptr: *const u8) -> bool;
-    fn dealloc(&mut self, ptr: *const u8);
+
+    /// The layout is the same one the pointer was allocated with.
+    fn dealloc(&mut self, ptr: *const u8, layout: Layout);
+
+    /// The number of bytes from the start of the page which have been written.
+    fn offset(&self) -> usize;
+
+    /// Erases everything written at or past the offset. Offsets past the current one are ignored.
+    fn rewind(&mut self, offset: usize);
+
+    /// Whether public notebooks may hand out the rest of a page without moving the offset.
+    /// Utensils which can only free at the offset must see every allocation.
+    #[doc(hidden)]
+    const LOCK_FREE: bool = true;
 }
 
 /// You cannot erase ink.
@@ -127,15 +175,19 @@ impl Utensil for Pen {
     }
 
     #[inline(always)]
-    fn can_alloc(&self, bytes: usize) -> bool {
-        self.remaining() >= bytes
+    fn can_alloc(&self, layout: Layout) -> bool {
+        let pad = padding(self.addr + self.offset, layout.align());
+
+        self.remaining() >= pad && self.remaining() - pad >= layout.size()
     }
 
     #[inline(always)]
-    fn alloc(&mut self, bytes: usize) -> *mut u8 {
+    fn alloc(&mut self, layout: Layout) -> *mut u8 {
+        self.offset += padding(self.addr + self.offset, layout.align());
+
         let t = self.addr + self.offset;
 
-        self.offset += bytes;
+        self.offset += layout.size();
         t as *mut u8
     }
 
@@ -145,13 +197,262 @@ impl Utensil for Pen {
     }
 
     #[inline(always)]
-    fn dealloc(&mut self, _: *const u8) {
+    fn dealloc(&mut self, _: *const u8, _: Layout) {
+    }
+
+    #[inline(always)]
+    fn offset(&self) -> usize {
+        self.offset
+    }
+
+    #[inline(always)]
+    fn rewind(&mut self, offset: usize) {
+        self.offset = self.offset.min(offset);
+    }
+}
+
+/// You can erase pencil.
+///
+/// This causes the notebook to keep track of deallocated memory. Erased blocks are kept in a list
+/// sorted by offset, adjacent blocks are merged together, and a block which reaches the end of the
+/// written part of the page is given back to the offset. Allocations first look for an erased
+/// block large enough to hold the bytes before falling back to bump allocation. This is slower than
+/// the pen, but long lived notebooks no longer leak the memory of dropped values.
+pub struct Pencil {
+    addr: usize,
+    layout: Layout,
+    offset: usize,
+    /// (offset, bytes) of every erased block
+    erased: Vec<(usize, usize)>,
+}
+
+impl Sealed for Pencil {}
+
+impl Pencil {
+    #[inline(always)]
+    fn remaining(&self) -> usize {
+        self.layout.size() - self.offset
+    }
+
+    /// The index of the first erased block which fits the layout and the padding to align it.
+    fn find_erased(&self, layout: Layout) -> Option<(usize, usize)> {
+        self.erased.iter().enumerate().find_map(|(idx, &(offset, len))| {
+            let pad = padding(self.addr + offset, layout.align());
+
+            if len >= pad && len - pad >= layout.size() {
+                Some((idx, pad))
+            } else {
+                None
+            }
+        })
+    }
+}
+
+impl Utensil for Pencil {
+    fn new(addr: usize, layout: Layout) -> Self {
+        Pencil {
+            addr,
+            layout,
+            offset: 0,
+            erased: Vec::new(),
+        }
+    }
+
+    #[inline(always)]
+    fn can_alloc(&self, layout: Layout) -> bool {
+        let pad = padding(self.addr + self.offset, layout.align());
+
+        (self.remaining() >= pad && self.remaining() - pad >= layout.size())
+            || self.find_erased(layout).is_some()
+    }
+
+    fn alloc(&mut self, layout: Layout) -> *mut u8 {
+        let bytes = layout.size();
+
+        if bytes > 0 {
+            if let Some((idx, pad)) = self.find_erased(layout) {
+                let (offset, len) = self.erased[idx];
+                let start = offset + pad;
+                let tail = len - pad - bytes;
+
+                // the padding before and the rest after the allocation stay erased
+                match (pad > 0, tail > 0) {
+                    (false, false) => {
+                        self.erased.remove(idx);
+                    }
+                    (false, true) => self.erased[idx] = (start + bytes, tail),
+                    (true, false) => self.erased[idx] = (offset, pad),
+                    (true, true) => {
+                        self.erased[idx] = (offset, pad);
+                        self.erased.insert(idx + 1, (start + bytes, tail));
+                    }
+                }
+
+                return (self.addr + start) as *mut u8;
+            }
+        }
+
+        let pad = padding(self.addr + self.offset, layout.align());
+
+        if pad > 0 && bytes > 0 {
+            self.erased.push((self.offset, pad));
+        }
+
+        self.offset += pad;
+
+        let t = self.addr + self.offset;
+
+        self.offset += bytes;
+        t as *mut u8
+    }
+
+    #[inline(always)]
+    fn can_dealloc(&self, ptr: *const u8) -> bool {
+        let addr = ptr as usize;
+
+        addr >= self.addr && addr < self.addr + self.layout.size()
+    }
+
+    fn dealloc(&mut self, ptr: *const u8, layout: Layout) {
+        if layout.size() == 0 {
+            return;
+        }
+
+        let mut offset = ptr as usize - self.addr;
+        let mut len = layout.size();
+        let mut idx = self.erased.partition_point(|&(o, _)| o < offset);
+
+        // merge with the following block
+        if let Some(&(next_offset, next_len)) = self.erased.get(idx) {
+            if offset + len == next_offset {
+                len += next_len;
+                self.erased.remove(idx);
+            }
+        }
+
+        // merge with the preceding block
+        if idx > 0 {
+            let (prev_offset, prev_len) = self.erased[idx - 1];
+
+            if prev_offset + prev_len == offset {
+                offset = prev_offset;
+                len += prev_len;
+                idx -= 1;
+                self.erased.remove(idx);
+            }
+        }
+
+        if offset + len == self.offset {
+            self.offset = offset;
+        } else {
+            self.erased.insert(idx, (offset, len));
+        }
+    }
+
+    #[inline(always)]
+    fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn rewind(&mut self, offset: usize) {
+        if offset >= self.offset {
+            return;
+        }
+
+        self.offset = offset;
+        self.erased.retain(|&(o, _)| o < offset);
+
+        if let Some(&(last_offset, last_len)) = self.erased.last() {
+            if last_offset + last_len >= offset {
+                self.offset = last_offset;
+                self.erased.pop();
+            }
+        }
+    }
+}
+
+/// You can erase the last thing you wrote.
+///
+/// This causes the notebook to use stack allocation. Allocating works the same as the pen, but
+/// deallocating the most recent allocation gives its bytes back to the page. Deallocating anything
+/// else is a no-op and its bytes stay lost, even after everything written after it is deallocated,
+/// until the notebook is rewound to a marker or reset. This is nearly as fast as the pen and works
+/// well for scratch memory which is pushed and popped in order.
+///
+/// Public notebooks take the lock for every allocation with the eraser and never use thread
+/// chunks, otherwise the most recent allocation would not be at the offset.
+pub struct Eraser {
+    addr: usize,
+    layout: Layout,
+    offset: usize,
+}
+
+impl Sealed for Eraser {}
+
+impl Eraser {
+    #[inline(always)]
+    fn remaining(&self) -> usize {
+        self.layout.size() - self.offset
+    }
+}
+
+impl Utensil for Eraser {
+    const LOCK_FREE: bool = false;
+
+    fn new(addr: usize, layout: Layout) -> Self {
+        Eraser {
+            addr,
+            layout,
+            offset: 0,
+        }
+    }
+
+    #[inline(always)]
+    fn can_alloc(&self, layout: Layout) -> bool {
+        let pad = padding(self.addr + self.offset, layout.align());
+
+        self.remaining() >= pad && self.remaining() - pad >= layout.size()
+    }
+
+    #[inline(always)]
+    fn alloc(&mut self, layout: Layout) -> *mut u8 {
+        self.offset += padding(self.addr + self.offset, layout.align());
+
+        let t = self.addr + self.offset;
+
+        self.offset += layout.size();
+        t as *mut u8
+    }
+
+    #[inline(always)]
+    fn can_dealloc(&self, ptr: *const u8) -> bool {
+        let addr = ptr as usize;
+
+        addr >= self.addr && addr < self.addr + self.layout.size()
+    }
+
+    #[inline(always)]
+    fn dealloc(&mut self, ptr: *const u8, layout: Layout) {
+        if ptr as usize + layout.size() == self.addr + self.offset {
+            self.offset = ptr as usize - self.addr;
+        }
+    }
+
+    #[inline(always)]
+    fn offset(&self) -> usize {
+        self.offset
+    }
+
+    #[inline(always)]
+    fn rewind(&mut self, offset: usize) {
+        self.offset = self.offset.min(offset);
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::mem::size_of;
+    use std::alloc::Layout;
+    use std::mem::{align_of, size_of};
 
     use crate::*;
 
@@ -163,7 +464,7 @@ mod tests {
             ).expect(line_str!())
         };
         ($layout:ty, $utensil:ty) => {
-            Page::<crate::$utensil>::create(
+            Page::<$utensil>::create(
                 std::alloc::Layout::new::<$layout>(),
                 &crate::StdAllocator,
             ).expect(line_str!())
@@ -172,60 +473,163 @@ mod tests {
 
     #[test]
     fn can_allocate_full_size() {
-        assert!(new_page!(usize).can_alloc(size_of::<usize>()));
+        assert!(new_page!(usize).can_alloc(Layout::new::<usize>()));
     }
 
     #[test]
     fn cannot_allocate_greater_than_full_size() {
-        assert!(!new_page!(usize).can_alloc(size_of::<usize>() + 1));
+        let layout = Layout::from_size_align(size_of::<usize>() + 1, align_of::<usize>());
+
+        assert!(!new_page!(usize).can_alloc(layout.expect(line_str!())));
     }
 
     #[test]
     fn can_allocate_multiple() {
         let mut page = new_page!([usize; 4]);
 
-        assert!(page.can_alloc(size_of::<usize>()));
-        page.alloc(size_of::<usize>());
-        assert!(page.can_alloc(size_of::<usize>()));
-        page.alloc(size_of::<usize>());
-        assert!(page.can_alloc(size_of::<usize>()));
-        page.alloc(size_of::<usize>());
-        assert!(page.can_alloc(size_of::<usize>()));
-        page.alloc(size_of::<usize>());
-        assert!(!page.can_alloc(size_of::<usize>()));
+        assert!(page.can_alloc(Layout::new::<usize>()));
+        page.alloc(Layout::new::<usize>());
+        assert!(page.can_alloc(Layout::new::<usize>()));
+        page.alloc(Layout::new::<usize>());
+        assert!(page.can_alloc(Layout::new::<usize>()));
+        page.alloc(Layout::new::<usize>());
+        assert!(page.can_alloc(Layout::new::<usize>()));
+        page.alloc(Layout::new::<usize>());
+        assert!(!page.can_alloc(Layout::new::<usize>()));
     }
 
     #[test]
     fn can_allocate_partial() {
         let mut page = new_page!(u128);
 
-        assert!(page.can_alloc(size_of::<u64>()));
-        page.alloc(size_of::<u64>());
-        assert!(page.can_alloc(size_of::<u32>()));
-        page.alloc(size_of::<u32>());
-        assert!(page.can_alloc(size_of::<u16>()));
-        page.alloc(size_of::<u16>());
-        assert!(page.can_alloc(size_of::<u8>()));
-        page.alloc(size_of::<u8>());
-        assert!(page.can_alloc(size_of::<u8>()));
-        page.alloc(size_of::<u8>());
-        assert!(!page.can_alloc(size_of::<u8>()));
+        assert!(page.can_alloc(Layout::new::<u64>()));
+        page.alloc(Layout::new::<u64>());
+        assert!(page.can_alloc(Layout::new::<u32>()));
+        page.alloc(Layout::new::<u32>());
+        assert!(page.can_alloc(Layout::new::<u16>()));
+        page.alloc(Layout::new::<u16>());
+        assert!(page.can_alloc(Layout::new::<u8>()));
+        page.alloc(Layout::new::<u8>());
+        assert!(page.can_alloc(Layout::new::<u8>()));
+        page.alloc(Layout::new::<u8>());
+        assert!(!page.can_alloc(Layout::new::<u8>()));
     }
 
     #[test]
     fn pen_can_always_deallocate() {
         let mut page = new_page!(u128);
 
-        assert!(page.can_alloc(size_of::<u64>()));
-        page.alloc(size_of::<u64>());
-        assert!(page.can_alloc(size_of::<u32>()));
-        page.alloc(size_of::<u32>());
-        assert!(page.can_alloc(size_of::<u16>()));
-        page.alloc(size_of::<u16>());
-        assert!(page.can_alloc(size_of::<u8>()));
-        page.alloc(size_of::<u8>());
-        assert!(page.can_alloc(size_of::<u8>()));
-        page.alloc(size_of::<u8>());
-        assert!(!page.can_alloc(size_of::<u8>()));
+        assert!(page.can_alloc(Layout::new::<u64>()));
+        page.alloc(Layout::new::<u64>());
+        assert!(page.can_alloc(Layout::new::<u32>()));
+        page.alloc(Layout::new::<u32>());
+        assert!(page.can_alloc(Layout::new::<u16>()));
+        page.alloc(Layout::new::<u16>());
+        assert!(page.can_alloc(Layout::new::<u8>()));
+        page.alloc(Layout::new::<u8>());
+        assert!(page.can_alloc(Layout::new::<u8>()));
+        page.alloc(Layout::new::<u8>());
+        assert!(!page.can_alloc(Layout::new::<u8>()));
+    }
+
+    #[test]
+    fn pen_pads_to_alignment() {
+        let mut page = new_page!([u64; 4]);
+        let a = page.alloc(Layout::new::<u8>());
+        let b = page.alloc(Layout::new::<u64>());
+
+        assert_eq!(a as usize + size_of::<u64>(), b as usize);
+        assert!(page.can_alloc(Layout::new::<[u64; 2]>()));
+        assert!(!page.can_alloc(Layout::new::<[u64; 3]>()));
+    }
+
+    #[test]
+    fn pencil_pads_erased_block_to_alignment() {
+        let mut page = new_page!([u64; 4], Pencil);
+        let a = page.alloc(Layout::new::<u32>());
+        page.alloc(Layout::new::<[u32; 3]>());
+        page.alloc(Layout::new::<[u64; 2]>());
+
+        page.dealloc(a, Layout::new::<u32>());
+        assert!(!page.can_alloc(Layout::new::<u64>()));
+        assert!(page.can_alloc(Layout::new::<u32>()));
+    }
+
+    #[test]
+    fn pencil_reuses_erased_block() {
+        let mut page = new_page!([usize; 4], Pencil);
+        let a = page.alloc(Layout::new::<usize>());
+        let b = page.alloc(Layout::new::<usize>());
+        page.alloc(Layout::new::<usize>());
+        page.alloc(Layout::new::<usize>());
+
+        assert!(!page.can_alloc(Layout::new::<usize>()));
+        page.dealloc(b, Layout::new::<usize>());
+        assert!(page.can_alloc(Layout::new::<usize>()));
+        assert!(!page.can_alloc(Layout::new::<[usize; 2]>()));
+        page.dealloc(a, Layout::new::<usize>());
+        assert!(page.can_alloc(Layout::new::<[usize; 2]>()));
+        assert_eq!(a, page.alloc(Layout::new::<[usize; 2]>()));
+        assert!(!page.can_alloc(Layout::new::<usize>()));
+    }
+
+    #[test]
+    fn pencil_returns_erased_tail_to_offset() {
+        let mut page = new_page!([usize; 4], Pencil);
+        page.alloc(Layout::new::<usize>());
+        let b = page.alloc(Layout::new::<usize>());
+        let c = page.alloc(Layout::new::<usize>());
+
+        page.dealloc(b, Layout::new::<usize>());
+        page.dealloc(c, Layout::new::<usize>());
+        assert!(page.can_alloc(Layout::new::<[usize; 3]>()));
+        assert_eq!(b, page.alloc(Layout::new::<[usize; 3]>()));
+    }
+
+    #[test]
+    fn pencil_rewind_drops_erased_blocks() {
+        let mut page = new_page!([usize; 4], Pencil);
+        page.alloc(Layout::new::<usize>());
+        let b = page.alloc(Layout::new::<usize>());
+        page.alloc(Layout::new::<usize>());
+
+        page.dealloc(b, Layout::new::<usize>());
+        page.rewind(2 * size_of::<usize>());
+        assert_eq!(size_of::<usize>(), page.offset());
+        assert!(page.can_alloc(Layout::new::<[usize; 3]>()));
+        assert!(!page.can_alloc(Layout::new::<[usize; 4]>()));
+    }
+
+    #[test]
+    fn pencil_only_deallocates_own_memory() {
+        let page = new_page!(usize, Pencil);
+        let other = new_page!(usize, Pencil);
+
+        assert!(page.can_dealloc(page.ptr.as_ptr()));
+        assert!(!page.can_dealloc(other.ptr.as_ptr()));
+    }
+
+    #[test]
+    fn eraser_pops_most_recent() {
+        let mut page = new_page!([usize; 4], Eraser);
+        let a = page.alloc(Layout::new::<usize>());
+        let b = page.alloc(Layout::new::<usize>());
+
+        page.dealloc(a, Layout::new::<usize>());
+        assert_eq!(2 * size_of::<usize>(), page.offset());
+        page.dealloc(b, Layout::new::<usize>());
+        assert_eq!(size_of::<usize>(), page.offset());
+        assert_eq!(b, page.alloc(Layout::new::<usize>()));
+    }
+
+    #[test]
+    fn rewind_never_moves_forward() {
+        let mut page = new_page!([usize; 4], Eraser);
+        page.alloc(Layout::new::<usize>());
+
+        page.rewind(3 * size_of::<usize>());
+        assert_eq!(size_of::<usize>(), page.offset());
+        page.rewind(0);
+        assert_eq!(0, page.offset());
     }
 }
@@ -0,0 +1,190 @@
+use std::alloc::Layout;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ptr::NonNull;
+use std::sync::RwLock;
+use std::thread;
+
+use crate::allocator::BookcaseAllocator;
+use crate::error::Error;
+use crate::lock::RawLock;
+use crate::notebook::{Notebook, PublicMultiNotebook};
+use crate::page::Utensil;
+use crate::seal::Sealed;
+use crate::strategy::{GrowthStrategy, SizeStrategy};
+
+thread_local! {
+    /// Hashed once per thread so picking a shard stays cheap.
+    static THREAD_HASH: usize = {
+        let mut hasher = DefaultHasher::new();
+
+        thread::current().id().hash(&mut hasher);
+        hasher.finish() as usize
+    };
+}
+
+/// Spreads threads over several public notebooks by their thread id, so threads mostly allocate
+/// in different notebooks and rarely wait for each other. Values are given back to the notebook
+/// they were allocated in, whichever thread frees them.
+pub struct ShardedNotebook<A: BookcaseAllocator, U: Utensil, L = RwLock<()>> {
+    shards: Vec<PublicMultiNotebook<A, U, L>>,
+}
+
+impl<A: BookcaseAllocator + Clone, U: Utensil, L: RawLock> ShardedNotebook<A, U, L> {
+    /// Every shard gets its own clone of the allocator. There is always at least one shard.
+    pub fn new(
+        allocator: A,
+        size: SizeStrategy,
+        growth: GrowthStrategy,
+        shards: usize,
+    ) -> ShardedNotebook<A, U, L> {
+        ShardedNotebook {
+            shards: (0..shards.max(1))
+                .map(|_| PublicMultiNotebook::<A, U, L>::new(allocator.clone(), size, growth))
+                .collect(),
+        }
+    }
+}
+
+impl<A: BookcaseAllocator, U: Utensil, L: RawLock> ShardedNotebook<A, U, L> {
+    /// Limits how large a page of any shard may grow. Values larger than this can't be allocated.
+    pub fn with_max_page_bytes(self, max_page_bytes: usize) -> Self {
+        self.map_shards(|shard| shard.with_max_page_bytes(max_page_bytes))
+    }
+
+    /// Gives every thread its own chunk of a page of its shard to allocate from.
+    pub fn with_thread_cache(self, chunk_bytes: usize) -> Self {
+        self.map_shards(|shard| shard.with_thread_cache(chunk_bytes))
+    }
+
+    fn map_shards<F>(self, f: F) -> Self
+    where
+        F: FnMut(PublicMultiNotebook<A, U, L>) -> PublicMultiNotebook<A, U, L>,
+    {
+        ShardedNotebook {
+            shards: self.shards.into_iter().map(f).collect(),
+        }
+    }
+
+    /// Every shard, in the order threads are spread over them.
+    pub fn shards(&self) -> &[PublicMultiNotebook<A, U, L>] {
+        &self.shards
+    }
+
+    /// The shard the current thread allocates in.
+    pub fn shard(&self) -> &PublicMultiNotebook<A, U, L> {
+        &self.shards[self.shard_idx()]
+    }
+
+    #[inline(always)]
+    fn shard_idx(&self) -> usize {
+        // a thread that is being torn down has no hash left, so it shares the first shard
+        THREAD_HASH.try_with(|hash| *hash).unwrap_or(0) % self.shards.len()
+    }
+
+    /// Erases everything in every shard while keeping every page.
+    pub fn reset(&mut self) {
+        for shard in self.shards.iter_mut() {
+            shard.reset();
+        }
+    }
+}
+
+impl<A: BookcaseAllocator, U: Utensil, L: RawLock> Sealed for ShardedNotebook<A, U, L> {}
+
+impl<A: BookcaseAllocator, U: Utensil, L: RawLock> Notebook for ShardedNotebook<A, U, L> {
+    #[inline(always)]
+    fn try_alloc_layout(&self, layout: Layout) -> Result<NonNull<u8>, Error> {
+        self.shard().try_alloc_layout(layout)
+    }
+
+    /// Values are mostly freed by the thread that allocated them, so its shard is tried first.
+    #[inline(always)]
+    unsafe fn dealloc_layout(&self, ptr: NonNull<u8>, layout: Layout) -> bool {
+        let idx = self.shard_idx();
+
+        self.shards[idx].dealloc_layout(ptr, layout)
+            || self
+                .shards
+                .iter()
+                .enumerate()
+                .any(|(other, shard)| other != idx && shard.dealloc_layout(ptr, layout))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use std::thread;
+
+    use crate::*;
+
+    fn new_sharded(shards: usize) -> ShardedNotebook<StdAllocator, Pencil> {
+        ShardedNotebook::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(8),
+            GrowthStrategy::Constant,
+            shards,
+        )
+    }
+
+    #[test]
+    fn test_threads_are_spread_over_shards() {
+        let notebook = Arc::new(new_sharded(4));
+
+        let workers: Vec<_> = (0..16u64)
+            .map(|t| {
+                let notebook = notebook.clone();
+
+                thread::spawn(move || {
+                    let value = notebook.alloc_init(t).expect(line_str!());
+
+                    assert_eq!(t, *value);
+                    notebook.shard() as *const PublicMultiNotebook<_, _> as usize
+                })
+            })
+            .collect();
+
+        let shards: HashSet<usize> = workers
+            .into_iter()
+            .map(|worker| worker.join().expect(line_str!()))
+            .collect();
+
+        assert!(shards.len() > 1);
+    }
+
+    #[test]
+    fn test_dealloc_is_routed_to_the_owning_shard() {
+        let notebook = Arc::new(new_sharded(64));
+
+        let addrs: Vec<usize> = (0..8u64)
+            .map(|t| {
+                let notebook = notebook.clone();
+
+                thread::spawn(move || {
+                    notebook.alloc_init(t).expect(line_str!()) as *mut u64 as usize
+                })
+            })
+            .map(|worker| worker.join().expect(line_str!()))
+            .collect();
+
+        for addr in addrs {
+            assert!(unsafe { notebook.dealloc(&*(addr as *const u64)) });
+        }
+
+        assert!(!unsafe { notebook.dealloc(&7u64) });
+    }
+
+    #[test]
+    fn test_handles_drop_through_their_shard() {
+        let notebook = new_sharded(2);
+        let shared = std::rc::Rc::new(());
+        let handle = notebook.new(shared.clone()).expect(line_str!());
+
+        assert_eq!(2, std::rc::Rc::strong_count(&shared));
+        drop(handle);
+        assert_eq!(1, std::rc::Rc::strong_count(&shared));
+        assert_eq!(1, new_sharded(0).shards().len());
+    }
+}
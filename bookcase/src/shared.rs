@@ -0,0 +1,148 @@
+use core::ops::Deref;
+use std::sync::Arc;
+
+use crate::error::NewError;
+use crate::handle::OwnedHandle;
+use crate::notebook::TypedNotebook;
+
+/// A notebook owned by every clone of it and by every handle allocated through it. Handles can be
+/// stored anywhere and moved to other threads, and the pages are freed when the last clone and
+/// the last handle are dropped. Share a public notebook to move handles between threads.
+pub struct SharedNotebook<N> {
+    notebook: Arc<N>,
+}
+
+impl<N> SharedNotebook<N> {
+    pub fn new(notebook: N) -> SharedNotebook<N> {
+        SharedNotebook {
+            notebook: Arc::new(notebook),
+        }
+    }
+
+    /// Gives back the notebook if no other clone or handle is left, otherwise gives back the
+    /// shared notebook.
+    pub fn try_into_inner(self) -> Result<N, SharedNotebook<N>> {
+        Arc::try_unwrap(self.notebook).map_err(|notebook| SharedNotebook { notebook })
+    }
+
+    /// Moves the value into the notebook. The value is handed back if allocation fails.
+    pub fn try_new_owned<T>(&self, t: T) -> Result<OwnedHandle<T, N>, NewError<T>>
+    where
+        N: TypedNotebook<T>,
+    {
+        match self.notebook.try_alloc_uninit_t() {
+            Ok(slot) => {
+                let t = slot.write(t);
+
+                // the value was just allocated and is only referenced by the handle
+                Ok(unsafe { OwnedHandle::new(self.notebook.clone(), t) })
+            }
+            Err(error) => Err(NewError { error, value: t }),
+        }
+    }
+
+    /// Moves the value into the notebook. The value is dropped if allocation fails.
+    pub fn new_owned<T>(&self, t: T) -> Option<OwnedHandle<T, N>>
+    where
+        N: TypedNotebook<T>,
+    {
+        self.try_new_owned(t).ok()
+    }
+}
+
+impl<N> Clone for SharedNotebook<N> {
+    fn clone(&self) -> Self {
+        SharedNotebook {
+            notebook: self.notebook.clone(),
+        }
+    }
+}
+
+impl<N> Deref for SharedNotebook<N> {
+    type Target = N;
+
+    fn deref(&self) -> &Self::Target {
+        &self.notebook
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    use crate::*;
+
+    struct Counted(Arc<AtomicUsize>);
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_owned_handles_move_to_threads() {
+        let notebook = SharedNotebook::new(PublicMultiNotebook::<_, Pencil>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(8),
+            GrowthStrategy::Constant,
+        ));
+        let drops = Arc::new(AtomicUsize::new(0));
+
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                let handle = notebook.new_owned(Counted(drops.clone())).expect(line_str!());
+
+                thread::spawn(move || drop(handle))
+            })
+            .collect();
+
+        for worker in workers {
+            worker.join().expect(line_str!());
+        }
+
+        assert_eq!(4, drops.load(Ordering::Relaxed));
+        assert!(notebook.try_into_inner().is_ok());
+    }
+
+    #[test]
+    fn test_handles_keep_the_notebook_alive() {
+        struct Holder {
+            name: OwnedHandle<String, PersonalMonoNotebook<StdAllocator, Pen, String>>,
+        }
+
+        let holder = {
+            let notebook = SharedNotebook::new(PersonalMonoNotebook::<_, Pen, String>::new(
+                StdAllocator,
+                SizeStrategy::ItemsPerPage(4),
+                GrowthStrategy::Constant,
+            ));
+
+            Holder {
+                name: notebook.new_owned(String::from("kept")).expect(line_str!()),
+            }
+        };
+
+        assert_eq!("kept", *holder.name);
+    }
+
+    #[test]
+    fn test_notebook_is_returned_after_last_handle() {
+        let notebook = SharedNotebook::new(PersonalMultiNotebook::<_, Pen>::new(
+            StdAllocator,
+            SizeStrategy::WordsPerPage(8),
+            GrowthStrategy::Constant,
+        ));
+        let handle = notebook.new_owned(7u64).expect(line_str!());
+        let notebook = notebook.try_into_inner().err().expect(line_str!());
+
+        assert_eq!(7, *handle);
+        drop(handle);
+
+        let notebook = notebook.try_into_inner().ok().expect(line_str!());
+
+        assert_eq!(1, *notebook.alloc_init(1u64).expect(line_str!()));
+    }
+}
@@ -1,5 +1,10 @@
+use core::convert::TryFrom;
 use core::mem::size_of;
 
+/// The largest page a notebook will create unless configured otherwise. This is the largest size
+/// a `Layout` allows.
+pub const MAX_PAGE_BYTES: usize = isize::MAX as usize;
+
 /// This controls the base size of memory allocated for each page.
 #[derive(Clone, Copy, Debug)]
 pub enum SizeStrategy {
@@ -14,17 +19,19 @@ pub enum SizeStrategy {
 }
 
 impl SizeStrategy {
+    /// Saturates at usize::MAX instead of overflowing.
     #[inline(always)]
     pub(crate) fn base_bytes(&self, t_size: usize, t_align: usize) -> usize {
         match *self {
-            SizeStrategy::AlignmentsPerPage(n) => n * t_align,
-            SizeStrategy::ItemsPerPage(n) => n * t_size,
-            SizeStrategy::WordsPerPage(n) => n * size_of::<usize>(),
+            SizeStrategy::AlignmentsPerPage(n) => n.saturating_mul(t_align),
+            SizeStrategy::ItemsPerPage(n) => n.saturating_mul(t_size),
+            SizeStrategy::WordsPerPage(n) => n.saturating_mul(size_of::<usize>()),
         }
     }
 }
 
-/// This controls the growth rate of each page.
+/// This controls the growth rate of each page. Pages stop growing once they reach the maximum
+/// page size of the notebook.
 #[derive(Clone, Copy, Debug)]
 pub enum GrowthStrategy {
     /// size_strategy bytes
@@ -38,12 +45,52 @@ pub enum GrowthStrategy {
 }
 
 impl GrowthStrategy {
+    /// Saturates at max_bytes instead of overflowing.
     #[inline(always)]
-    pub(crate) fn page_bytes(&self, base_bytes: usize, page_idx: usize) -> usize {
-        match *self {
+    pub(crate) fn page_bytes(&self, base_bytes: usize, page_idx: usize, max_bytes: usize) -> usize {
+        let bytes = match *self {
             GrowthStrategy::Constant => base_bytes,
-            GrowthStrategy::Linear(n) => base_bytes * n * (page_idx + 1),
-            GrowthStrategy::Exponential => base_bytes << page_idx,
-        }
+            GrowthStrategy::Linear(n) => base_bytes
+                .saturating_mul(n)
+                .saturating_mul(page_idx.saturating_add(1)),
+            GrowthStrategy::Exponential => u32::try_from(page_idx)
+                .ok()
+                .and_then(|idx| 1usize.checked_shl(idx))
+                .and_then(|factor| base_bytes.checked_mul(factor))
+                .unwrap_or(usize::MAX),
+        };
+
+        bytes.min(max_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn base_bytes_saturates() {
+        assert_eq!(usize::MAX, SizeStrategy::ItemsPerPage(usize::MAX).base_bytes(2, 1));
+        assert_eq!(usize::MAX, SizeStrategy::WordsPerPage(usize::MAX / 2).base_bytes(1, 1));
+    }
+
+    #[test]
+    fn exponential_growth_saturates_at_max() {
+        let growth = GrowthStrategy::Exponential;
+
+        assert_eq!(32, growth.page_bytes(8, 2, 1024));
+        assert_eq!(1024, growth.page_bytes(8, 7, 1024));
+        assert_eq!(1024, growth.page_bytes(8, 63, 1024));
+        assert_eq!(1024, growth.page_bytes(8, 64, 1024));
+        assert_eq!(MAX_PAGE_BYTES, growth.page_bytes(8, 1000, MAX_PAGE_BYTES));
+    }
+
+    #[test]
+    fn linear_growth_saturates_at_max() {
+        let growth = GrowthStrategy::Linear(usize::MAX);
+
+        assert_eq!(64, growth.page_bytes(8, 0, 64));
+        assert_eq!(0, growth.page_bytes(0, usize::MAX, 64));
+        assert_eq!(MAX_PAGE_BYTES, growth.page_bytes(8, usize::MAX, MAX_PAGE_BYTES));
     }
 }
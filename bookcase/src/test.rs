@@ -4,3 +4,110 @@ macro_rules! line_str {
         &format!("expected on line: {}", line!())
     };
 }
+
+/// Fails to compile when the type is Sync, since both impls of the trait would then apply.
+#[macro_export]
+macro_rules! assert_not_sync {
+    ($t:ty) => {{
+        trait AmbiguousIfSync<A> {
+            fn check() {}
+        }
+
+        impl<T: ?Sized> AmbiguousIfSync<()> for T {}
+
+        impl<T: ?Sized + Sync> AmbiguousIfSync<u8> for T {}
+
+        <$t as AmbiguousIfSync<_>>::check();
+    }};
+}
+
+#[cfg(not(feature = "allocator_api"))]
+mod stable {
+    use std::alloc::Layout;
+    use std::ptr::NonNull;
+
+    use crate::allocator::BookcaseAllocator;
+    use crate::error::Error;
+
+    use super::{FailingAllocator, PanickingAllocator};
+
+    macro_rules! impl_test_allocator {
+        ($allocator:ty, $allocate:expr) => {
+            impl BookcaseAllocator for $allocator {
+                fn allocate(&self, _: Layout) -> Result<NonNull<[u8]>, Error> {
+                    $allocate
+                }
+
+                fn allocate_zeroed(&self, _: Layout) -> Result<NonNull<[u8]>, Error> {
+                    $allocate
+                }
+
+                unsafe fn deallocate(&self, _: NonNull<u8>, _: Layout) {}
+
+                unsafe fn grow(
+                    &self,
+                    _: NonNull<u8>,
+                    _: Layout,
+                    _: Layout,
+                ) -> Result<NonNull<[u8]>, Error> {
+                    $allocate
+                }
+
+                unsafe fn grow_zeroed(
+                    &self,
+                    _: NonNull<u8>,
+                    _: Layout,
+                    _: Layout,
+                ) -> Result<NonNull<[u8]>, Error> {
+                    $allocate
+                }
+
+                unsafe fn shrink(
+                    &self,
+                    _: NonNull<u8>,
+                    _: Layout,
+                    _: Layout,
+                ) -> Result<NonNull<[u8]>, Error> {
+                    $allocate
+                }
+
+                fn by_ref(&self) -> &Self {
+                    self
+                }
+            }
+        };
+    }
+
+    impl_test_allocator!(FailingAllocator, Err(Error::AllocError));
+    impl_test_allocator!(PanickingAllocator, panic!("allocation panicked"));
+}
+
+#[cfg(feature = "allocator_api")]
+mod nightly {
+    use std::alloc::{AllocError, Allocator, Layout};
+    use std::ptr::NonNull;
+
+    use super::{FailingAllocator, PanickingAllocator};
+
+    unsafe impl Allocator for FailingAllocator {
+        fn allocate(&self, _: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            Err(AllocError)
+        }
+
+        unsafe fn deallocate(&self, _: NonNull<u8>, _: Layout) {}
+    }
+
+    unsafe impl Allocator for PanickingAllocator {
+        fn allocate(&self, _: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            panic!("allocation panicked")
+        }
+
+        unsafe fn deallocate(&self, _: NonNull<u8>, _: Layout) {}
+    }
+}
+
+/// Never gives out memory.
+pub(crate) struct FailingAllocator;
+
+/// Panics instead of giving out memory.
+pub(crate) struct PanickingAllocator;